@@ -3,18 +3,19 @@ extern crate concurrentes;
 use concurrentes::process;
 
 fn main() {
-  let result = process::fork();
+  let result = process::spawn_fn(|| {
+    println!("Child process");
+    Ok(())
+  });
   match result {
-    Ok(process::ForkResult::Parent{child}) => {
-      println!("Parent process of {:?}", child);
-      process::waitpid(child).expect(format!("Error while waiting {}", child).as_str());
-      println!("Child joined")
-    },
-    Ok(process::ForkResult::Child) => {
-      println!("Child process");
+    Ok(mut child) => {
+      println!("Parent process of {:?}", child.pid());
+      let status = child.join()
+        .unwrap_or_else(|e| panic!("Error while waiting {}: {}", child.pid(), e));
+      println!("Child joined: {}", status)
     },
     Err(err) => {
       println!("{:?}", err.kind());
     }
   }
-}
\ No newline at end of file
+}
@@ -1,7 +1,6 @@
 extern crate concurrentes;
 
-use concurrentes::ipc;
-use concurrentes::ipc::Key;
+use concurrentes::ipc::{IpcOptions, Key};
 use concurrentes::ipc::shmem::Shmem;
 use concurrentes::process;
 use std::io;
@@ -11,8 +10,9 @@ const KEY_FILE : &str = "/bin/bash";
 fn main() -> io::Result<()> {
   let key = Key::ftok(KEY_FILE, 0)?;
   println!("Key obtained: {}", key.key);
-  let flags = ipc::IPC_CREAT | ipc::IPC_EXCL | 0o660;
-  let mut shared_ints = Shmem::<i32>::get(&key, 5, flags)
+  let mut options = IpcOptions::new();
+  options.create(true).exclusive(true);
+  let mut shared_ints = Shmem::<i32>::get(&key, 5, &options)
     .expect("Error creating shared_ints");
   shared_ints.attach(0)?;
 
@@ -3,10 +3,9 @@ extern crate libc;
 
 use concurrentes::signal::{SignalHandler, SignalHandlerDispatcher};
 
-use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 use std::{thread, time};
 use std::io;
-use std::cell::RefCell;
 
 use libc::SIGINT;
 
@@ -32,14 +31,15 @@ impl SigIntHandler {
 }
 
 fn main() -> io::Result<()> {
-  // Use RC so I can keep one reference here in main and one in the handler
-  let sigint_handler = Rc::new(RefCell::new(SigIntHandler::new()));
-  SignalHandlerDispatcher::register(SIGINT, sigint_handler.clone());
+  // Use Arc so I can keep one reference here in main and one in the handler
+  let sigint_handler = Arc::new(Mutex::new(SigIntHandler::new()));
+  let _registration = SignalHandlerDispatcher::register(SIGINT, sigint_handler.clone())?;
 
-  while !sigint_handler.borrow().has_graceful_quit() {
+  while !sigint_handler.lock().unwrap().has_graceful_quit() {
     println!("Running");
     let millis = time::Duration::from_millis(500);
     thread::sleep(millis);
+    SignalHandlerDispatcher::dispatch_pending();
   }
   println!("Exit");
   Ok(())
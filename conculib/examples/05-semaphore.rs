@@ -1,7 +1,6 @@
 extern crate concurrentes;
 
-use concurrentes::ipc;
-use concurrentes::ipc::Key;
+use concurrentes::ipc::{IpcOptions, Key};
 use concurrentes::ipc::semaphore::Semaphore;
 use concurrentes::process;
 
@@ -10,31 +9,26 @@ use std::io;
 use std::{thread, time};
 
 fn main() -> io::Result<()> {
-  let key = Key::ftok(file!(), 0)?;
-  println!("Key obtained: {}", key.key);
-  let flags = ipc::IPC_CREAT | ipc::IPC_EXCL | 0o660;
-  let mut semaphore = Semaphore::get(&key, flags)?;
+  // El semáforo se crea antes del fork, por lo que alcanza con una clave privada
+  let key = Key::private();
+  let mut options = IpcOptions::new();
+  options.create(true).exclusive(true);
+  let mut semaphore = Semaphore::get(&key, &options)?;
   semaphore.init(0)?;
 
   // Parent reads shared memory after child writes
-  let fork_result = process::fork()?;
-  return match fork_result {
-    process::ForkResult::Parent{child} => {
-      println!("Parent process of {:?}", child);
-      semaphore.wait()?;
-      println!("Signal received");
-      process::waitpid(child).expect(format!("Error while waiting {}", child).as_str());
-      semaphore.remove();
-      println!("Child joined");
-      Ok(())
-    },
-    process::ForkResult::Child => {
-      println!("Child process");
-      let millis = time::Duration::from_millis(500);
-      thread::sleep(millis);
-      println!("Signal send");
-      semaphore.signal()
-    }
-  }
-  
+  let mut child = process::spawn_fn(|| {
+    println!("Child process");
+    let millis = time::Duration::from_millis(500);
+    thread::sleep(millis);
+    println!("Signal send");
+    semaphore.signal()
+  })?;
+  println!("Parent process of {:?}", child.pid());
+  semaphore.wait()?;
+  println!("Signal received");
+  child.join()?;
+  semaphore.remove();
+  println!("Child joined");
+  Ok(())
 }
@@ -0,0 +1,43 @@
+extern crate concurrentes;
+
+use concurrentes::ipc::IpcOptions;
+use concurrentes::patterns::{Barrier, Multiplex};
+use concurrentes::process;
+
+use std::io;
+use std::{thread, time};
+
+const NUM_CHILDREN : i32 = 3;
+const MULTIPLEX_KEY_FILE : &str = "/bin/bash";
+
+fn main() -> io::Result<()> {
+  let mut options = IpcOptions::new();
+  options.create(true).exclusive(true);
+  let mut barrier = Barrier::get(file!(), NUM_CHILDREN, &options)?;
+  barrier.init()?;
+  // El multiplex usa otro archivo de clave para no pisar los semáforos de la barrera
+  let mut multiplex = Multiplex::get(MULTIPLEX_KEY_FILE, &options)?;
+  multiplex.init(2)?;
+
+  let mut children = Vec::new();
+  for child_id in 0..NUM_CHILDREN {
+    children.push(process::spawn_fn(|| {
+      for round in 0..2 {
+        multiplex.enter()?;
+        println!("Child {} in critical section (round {})", child_id, round);
+        thread::sleep(time::Duration::from_millis(200));
+        multiplex.leave()?;
+        barrier.wait()?;
+        println!("Child {} passed barrier (round {})", child_id, round);
+      }
+      Ok(())
+    })?);
+  }
+  for child in &mut children {
+    child.join()?;
+  }
+  println!("Children joined");
+  barrier.remove();
+  multiplex.remove();
+  Ok(())
+}
@@ -0,0 +1,30 @@
+extern crate concurrentes;
+
+use concurrentes::ipc::event_fd::{EventFd, EventFdMode};
+use concurrentes::process;
+
+use std::io;
+use std::{thread, time};
+
+fn main() -> io::Result<()> {
+  // El fd se hereda al hacer fork, por lo que ambos procesos comparten el contador
+  let wake_up = EventFd::new(0, EventFdMode::Semaphore)?;
+
+  let mut child = process::spawn_fn(|| {
+    println!("Child process");
+    thread::sleep(time::Duration::from_millis(500));
+    println!("Notifying 3 events");
+    wake_up.write(3)
+  })?;
+  println!("Parent process of {:?}", child.pid());
+  while !wake_up.poll(200)? {
+    println!("Waiting for events");
+  }
+  // En modo semáforo cada lectura consume un único evento
+  for _ in 0..3 {
+    println!("Event received: {}", wake_up.read()?);
+  }
+  child.join()?;
+  println!("Child joined");
+  Ok(())
+}
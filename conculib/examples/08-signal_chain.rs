@@ -0,0 +1,62 @@
+extern crate concurrentes;
+extern crate libc;
+
+use concurrentes::signal::{SignalHandler, SignalHandlerDispatcher, SignalInfo};
+use concurrentes::signal;
+
+use std::io;
+use std::process;
+use std::sync::{Arc, Mutex};
+
+struct Counter {
+  name: &'static str,
+  received: u32
+}
+
+impl SignalHandler for Counter {
+  fn handle(&mut self) {}
+
+  fn handle_info(&mut self, info: &SignalInfo) {
+    println!("{} received signal {} from {} with value {}", self.name, info.signal, info.pid,
+      info.value);
+    self.received += 1;
+  }
+}
+
+fn counter(name: &'static str) -> Arc<Mutex<Counter>> {
+  Arc::new(Mutex::new(Counter {name, received: 0}))
+}
+
+fn main() -> io::Result<()> {
+  let command = signal::rt_signal(0).expect("No realtime signals available");
+  let pid = process::id() as i32;
+  let first = counter("first");
+  let second = counter("second");
+  // Both handlers are chained for the same signal
+  let first_registration = SignalHandlerDispatcher::register(command, first.clone())?;
+  let second_registration = SignalHandlerDispatcher::register(command, second.clone())?;
+  // Realtime signals are queued: each one is delivered
+  signal::queue(pid, command, 1)?;
+  signal::queue(pid, command, 2)?;
+  SignalHandlerDispatcher::dispatch_pending();
+  assert_eq!(first.lock().unwrap().received, 2);
+  assert_eq!(second.lock().unwrap().received, 2);
+
+  // Dropping a registration removes only that handler
+  drop(first_registration);
+  signal::queue(pid, command, 3)?;
+  SignalHandlerDispatcher::dispatch_pending();
+  assert_eq!(first.lock().unwrap().received, 2);
+  assert_eq!(second.lock().unwrap().received, 3);
+
+  // Removing the last one restores the default action
+  second_registration.unregister()?;
+  let action = unsafe {
+    let mut action: libc::sigaction = std::mem::zeroed();
+    libc::sigaction(command, std::ptr::null(), &mut action);
+    action
+  };
+  assert_eq!(action.sa_sigaction, libc::SIG_DFL);
+  println!("Done");
+  Ok(())
+}
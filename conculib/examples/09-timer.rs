@@ -0,0 +1,26 @@
+extern crate concurrentes;
+
+use concurrentes::timer::{Timers, TimerMode};
+
+use std::io;
+use std::time::Duration;
+
+fn main() -> io::Result<()> {
+  // Varios temporizadores independientes en el mismo proceso
+  let mut timers = Timers::new();
+  timers.start("tick", Duration::from_millis(100), TimerMode::Periodic)?;
+  timers.start("end", Duration::from_millis(550), TimerMode::OneShot)?;
+  let mut ticks = 0;
+  loop {
+    for event in timers.wait(None)? {
+      println!("Timer {} expired {} times", event.name, event.expirations);
+      if event.name == "tick" {
+        ticks += event.expirations;
+      } else {
+        timers.stop("tick")?;
+        println!("Done after {} ticks", ticks);
+        return Ok(());
+      }
+    }
+  }
+}
@@ -0,0 +1,52 @@
+extern crate concurrentes;
+
+use concurrentes::signal::{SignalHandler, SignalHandlerDispatcher, SignalInfo, SignalThread};
+use concurrentes::signal;
+
+use std::io;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::{thread, time};
+
+struct Counter {
+  values: Vec<i32>
+}
+
+impl SignalHandler for Counter {
+  fn handle(&mut self) {}
+
+  fn handle_info(&mut self, info: &SignalInfo) {
+    println!("Signal {} with value {} handled in thread {:?}", info.signal, info.value,
+      thread::current().name());
+    self.values.push(info.value);
+  }
+}
+
+fn main() -> io::Result<()> {
+  let command = signal::rt_signal(0).expect("No realtime signals available");
+  let counter = Arc::new(Mutex::new(Counter {values: Vec::new()}));
+  // Las señales se atienden en su propio hilo: nadie llama a dispatch_pending
+  let signal_thread = SignalThread::spawn(&[command])?;
+
+  // El manejador se registra desde otro hilo, y el registro es global
+  let registration = {
+    let counter = counter.clone();
+    thread::spawn(move || {
+      let registration = SignalHandlerDispatcher::register(command, counter).unwrap();
+      let pid = process::id() as i32;
+      for value in 1..4 {
+        signal::queue(pid, command, value).unwrap();
+      }
+      registration
+    }).join().unwrap()
+  };
+
+  while counter.lock().unwrap().values.len() < 3 {
+    thread::sleep(time::Duration::from_millis(10));
+  }
+  assert_eq!(counter.lock().unwrap().values, vec![1, 2, 3]);
+  drop(signal_thread);
+  registration.unregister()?;
+  println!("Done");
+  Ok(())
+}
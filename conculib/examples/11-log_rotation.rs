@@ -0,0 +1,57 @@
+#[macro_use(log)]
+extern crate concurrentes;
+
+use concurrentes::log::{GLOBAL_LOG, Log, LogRotation, LogSeverity};
+use concurrentes::process;
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::io::{BufRead, BufReader};
+
+const WRITERS: usize = 4;
+const LINES: usize = 200;
+const KEEP: usize = 100;
+
+fn main() -> io::Result<()> {
+  let path = std::env::temp_dir().join(format!("rotation-{}.log", std::process::id()));
+  let path = path.to_str().unwrap().to_string();
+  // Todos los procesos escriben el mismo log, que rota cada 2 KB
+  Log::init(&path, LogSeverity::DEBUG)?;
+  Log::init_rotation(LogRotation::new().max_bytes(2048).keep(KEEP))?;
+
+  let mut children = Vec::new();
+  for writer in 0..WRITERS {
+    children.push(process::spawn_fn(move || {
+      for line in 0..LINES {
+        log!(format!("writer {} line {}", writer, line).as_str(), &LogSeverity::INFO);
+      }
+      Ok(())
+    })?);
+  }
+  for child in &mut children {
+    child.join()?;
+  }
+
+  // Ninguna línea se perdió ni se duplicó entre los archivos rotados
+  let mut files = vec![path.clone()];
+  files.extend((1..KEEP + 1).map(|n| format!("{}.{}", path, n)));
+  let mut seen = HashSet::new();
+  let mut rotated = 0;
+  for file in &files {
+    let reader = match fs::File::open(file) {
+      Ok(reader) => BufReader::new(reader),
+      Err(_) => continue
+    };
+    rotated += 1;
+    for line in reader.lines() {
+      let line = line?;
+      let message = line.split(" - ").nth(1).unwrap().to_string();
+      assert!(seen.insert(message), "Duplicated line {}", line);
+    }
+    fs::remove_file(file)?;
+  }
+  assert_eq!(seen.len(), WRITERS * LINES);
+  println!("{} lines in {} files", seen.len(), rotated);
+  Ok(())
+}
@@ -0,0 +1,86 @@
+//! concu-ipcs
+//!
+//! Lista y elimina los IPCs anotados en el manifiesto de una corrida, de
+//! forma similar a `ipcs` / `ipcrm`, pero incluyendo FIFOs y archivos de lock.
+extern crate concurrentes;
+
+use concurrentes::ipc::registry::{Entry, Registry, DEFAULT_MANIFEST, MANIFEST_ENV};
+
+use std::env;
+use std::io;
+
+fn main() -> io::Result<()> {
+  let args: Vec<String> = env::args().collect();
+  let mut manifest = env::var(MANIFEST_ENV).unwrap_or_else(|_| DEFAULT_MANIFEST.to_string());
+  let mut command = "list".to_string();
+  let mut all = false;
+  let mut iter = args.iter().skip(1);
+  while let Some(arg) = iter.next() {
+    match arg.as_str() {
+      "-m" | "--manifest" => match iter.next() {
+        Some(path) => manifest = path.clone(),
+        None => return print_help(&args[0])
+      },
+      "-a" | "--all" => all = true,
+      "list" | "clean" => command = arg.clone(),
+      _ => return print_help(&args[0])
+    }
+  }
+  let mut registry = Registry::open(manifest.as_str())?;
+  if command == "list" {
+    list(&mut registry)
+  } else {
+    clean(&mut registry, all)
+  }
+}
+
+/// Lista los recursos del manifiesto junto al estado de su dueño
+fn list(registry: &mut Registry) -> io::Result<()> {
+  println!("{:>7} {:<7} {:<9} RECURSO", "DUEÑO", "ESTADO", "EXISTE");
+  for entry in registry.entries()? {
+    println!("{:>7} {:<7} {:<9} {}", entry.owner, owner_status(&entry),
+      if entry.resource.exists() { "si" } else { "no" }, entry.resource);
+  }
+  Ok(())
+}
+
+/// Elimina los recursos cuyo dueño ya no existe (o todos, si `all` es true)
+/// y quita del manifiesto los que ya no están en el sistema.
+fn clean(registry: &mut Registry, all: bool) -> io::Result<()> {
+  for entry in registry.entries()? {
+    let exists = entry.resource.exists();
+    if exists && !all && entry.is_owner_alive() {
+      continue;
+    }
+    if exists {
+      match entry.resource.remove() {
+        Ok(()) => println!("Eliminado {}", entry.resource),
+        Err(e) => {
+          println!("No se pudo eliminar {}: {}", entry.resource, e);
+          continue;
+        }
+      }
+    }
+    registry.forget(&entry.resource)?;
+  }
+  Ok(())
+}
+
+fn owner_status(entry: &Entry) -> &'static str {
+  if entry.is_owner_alive() {
+    "vivo"
+  } else {
+    "muerto"
+  }
+}
+
+fn print_help(program: &str) -> io::Result<()> {
+  println!("Usage: {} [-m <manifest>] [list | clean [-a]]", program);
+  println!();
+  println!("    list          lista los IPCs registrados (default)");
+  println!("    clean         elimina los IPCs cuyo proceso dueño terminó");
+  println!("    -a, --all     con clean, elimina todos los IPCs registrados");
+  println!("    -m, --manifest <path>");
+  println!("                  manifiesto a utilizar (default {})", DEFAULT_MANIFEST);
+  Ok(())
+}
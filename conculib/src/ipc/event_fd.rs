@@ -0,0 +1,143 @@
+use libc;
+use libc::{c_int, c_void};
+use std::io;
+use std::io::Error;
+use std::mem;
+use std::ops::Drop;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+/// Modos de lectura de un `EventFd`
+#[derive(Copy, Clone, Debug)]
+pub enum EventFdMode {
+  /// Cada lectura devuelve el valor acumulado del contador y lo reinicia a 0
+  Counter,
+  /// Cada lectura devuelve 1 y decrementa el contador en 1
+  Semaphore
+}
+
+/// Wrapper de `eventfd`, un contador de 64 bits manejado por el kernel
+///
+/// Sirve como notificación liviana entre procesos emparentados: el file
+/// descriptor se crea sin `close-on-exec`, por lo que es heredado por los
+/// hijos creados con `process::fork`. Las lecturas y escrituras son siempre
+/// de 8 bytes, por lo que no hace falta delimitar los mensajes.
+///
+/// Al ser un file descriptor, puede registrarse en `poll` junto a FIFOs u
+/// otros descriptores mediante `as_raw_fd`.
+pub struct EventFd {
+  fd: c_int
+}
+
+impl EventFd {
+  /// Llama a libc `eventfd(init_value, flags)`
+  /// * En caso de éxito devuelve un `EventFd` con su respectivo fd.
+  /// * En caso de error devuelve el error del sistema asociado.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use concurrentes::ipc::event_fd::{EventFd, EventFdMode};
+  ///
+  /// let event = EventFd::new(0, EventFdMode::Counter).unwrap();
+  /// event.write(2).unwrap();
+  /// assert_eq!(event.read().unwrap(), 2);
+  /// ```
+  pub fn new(init_value: u32, mode: EventFdMode) -> io::Result<EventFd> {
+    let flags = match mode {
+      EventFdMode::Counter => 0,
+      EventFdMode::Semaphore => libc::EFD_SEMAPHORE
+    };
+    let fd;
+    unsafe {
+      fd = libc::eventfd(init_value, flags);
+    }
+    if fd != -1 {
+      Ok(EventFd{fd})
+    } else {
+      Err(Error::last_os_error())
+    }
+  }
+
+  /// Lee el contador. Se bloquea si vale 0, salvo que el fd sea no bloqueante,
+  /// en cuyo caso devuelve un error de tipo `WouldBlock`.
+  pub fn read(&self) -> io::Result<u64> {
+    let mut value: u64 = 0;
+    let result;
+    unsafe {
+      result = libc::read(self.fd, &mut value as *mut u64 as *mut c_void, mem::size_of::<u64>());
+    }
+    if result >= 0 {
+      Ok(value)
+    } else {
+      Err(Error::last_os_error())
+    }
+  }
+
+  /// Suma `value` al contador, despertando a quien esté esperando leerlo
+  pub fn write(&self, value: u64) -> io::Result<()> {
+    let result;
+    unsafe {
+      result = libc::write(self.fd, &value as *const u64 as *const c_void, mem::size_of::<u64>());
+    }
+    if result >= 0 {
+      Ok(())
+    } else {
+      Err(Error::last_os_error())
+    }
+  }
+
+  /// Notifica un evento (suma uno al contador)
+  pub fn notify(&self) -> io::Result<()> {
+    self.write(1)
+  }
+
+  /// Activa o desactiva el modo no bloqueante del fd
+  pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+    let result;
+    unsafe {
+      let flags = libc::fcntl(self.fd, libc::F_GETFL);
+      let flags = if nonblocking {
+        flags | libc::O_NONBLOCK
+      } else {
+        flags & !libc::O_NONBLOCK
+      };
+      result = libc::fcntl(self.fd, libc::F_SETFL, flags);
+    }
+    if result != -1 {
+      Ok(())
+    } else {
+      Err(Error::last_os_error())
+    }
+  }
+
+  /// Espera hasta `timeout_ms` milisegundos a que haya un evento para leer
+  /// (-1 espera indefinidamente). Devuelve `true` si el contador es distinto
+  /// de 0, sin consumirlo.
+  pub fn poll(&self, timeout_ms: i32) -> io::Result<bool> {
+    let mut poll_fd = libc::pollfd {fd: self.fd, events: libc::POLLIN, revents: 0};
+    let result;
+    unsafe {
+      result = libc::poll(&mut poll_fd, 1, timeout_ms);
+    }
+    if result >= 0 {
+      Ok(poll_fd.revents & libc::POLLIN != 0)
+    } else {
+      Err(Error::last_os_error())
+    }
+  }
+}
+
+impl AsRawFd for EventFd {
+  fn as_raw_fd(&self) -> RawFd {
+    self.fd
+  }
+}
+
+impl Drop for EventFd {
+  /// Destructor: cierra el fd al salir
+  fn drop(&mut self) {
+    unsafe {
+      libc::close(self.fd);
+    }
+  }
+}
@@ -1,10 +1,16 @@
 use std::fs::{File, remove_file};
 use std::fs::OpenOptions;
 use std::io;
-use std::io::Error;
+use std::io::{Error, ErrorKind};
+use std::os::unix::fs::OpenOptionsExt;
 use std::os::unix::io::AsRawFd;
 use libc;
+use libc::{gid_t, uid_t};
 use ipc;
+use ipc::IpcOptions;
+use ipc::registry;
+use ipc::registry::Resource;
+use signal::EintrPolicy;
 
 /*
 pub struct FileLockGuard<'a> {
@@ -22,22 +28,57 @@ pub struct FileLockGuard<'a> {
 /// para acceder a su file descriptor (necesario para llamadas a la biblioteca libc)
 ///
 /// * `path`: Ruta correspondiente al FileLock
+///
+/// * `eintr`: Qué hacer si una señal interrumpe la espera del lock
 pub struct FileLock {
   pub file: File,
-  pub path: String
+  pub path: String,
+  eintr: EintrPolicy
 }
 
 impl FileLock {
   /// Crea un archivo y devuelve el FileLock correspondiente
   pub fn new_with_options(path: String, options: &OpenOptions)  -> io::Result<FileLock> {
     let file = options.open(path.as_str())?;
-    Ok(FileLock {file, path})
+    Ok(FileLock {file, path, eintr: EintrPolicy::Surface})
   }
 
   /// Abre, o crea si no existe un archivo, en modo lectura/escritura.
+  /// El archivo se anota en el registro de IPCs.
   pub fn create(path: String) -> io::Result<FileLock> {
-    let file = OpenOptions::new().read(true).write(true).create(true).open(path.as_str())?;
-    Ok(FileLock {file, path})
+    FileLock::open(path, IpcOptions::new().create(true).mode(0o666))
+  }
+
+  /// Abre el archivo `path` en modo lectura/escritura según `options`. Si se
+  /// pidió su creación y el archivo no existía, se anota en el registro de
+  /// IPCs; un archivo que ya existía pertenece a quien lo creó.
+  ///
+  /// # Example
+  ///
+  /// ```rust, no_run
+  /// use concurrentes::ipc::IpcOptions;
+  /// use concurrentes::ipc::flock::FileLock;
+  ///
+  /// let lock = FileLock::open("example.lock".to_string(),
+  ///   IpcOptions::new().create(true).exclusive(true).mode(0o600)).unwrap();
+  /// ```
+  pub fn open(path: String, options: &IpcOptions) -> io::Result<FileLock> {
+    let (file, created) = open_file(path.as_str(), options)?;
+    if created {
+      registry::record(Resource::lock_file(path.as_str()));
+    }
+    if let Some((owner, group)) = options.get_ownership() {
+      let result;
+      unsafe {
+        // -1 deja el valor sin modificar
+        result = libc::fchown(file.as_raw_fd(),
+          owner.unwrap_or(!0 as uid_t), group.unwrap_or(!0 as gid_t));
+      }
+      if result != 0 {
+        return Err(Error::last_os_error());
+      }
+    }
+    Ok(FileLock {file, path, eintr: EintrPolicy::Surface})
   }
 
   /// Aplica un lock exclusivo sobre todo el archivo
@@ -50,20 +91,43 @@ impl FileLock {
     self.flock(ipc::F_RDLCK)
   }
 
+  /// Intenta aplicar un lock exclusivo sobre todo el archivo sin bloquearse.
+  /// Devuelve `false` si otro proceso tiene el archivo lockeado.
+  pub fn try_lock_exclusive(&mut self) -> io::Result<bool> {
+    match self.fcntl_lock(ipc::F_WRLCK, libc::F_SETLK) {
+      Ok(()) => Ok(true),
+      Err(ref e) if e.raw_os_error() == Some(libc::EAGAIN)
+        || e.raw_os_error() == Some(libc::EACCES) => Ok(false),
+      Err(e) => Err(e)
+    }
+  }
+
   /// Quita el lock aplicado con `lock_shared` o `lock_exclusive` archivo
   pub fn unlock(&mut self) -> io::Result<()> {
     self.flock(ipc::F_UNLCK)
   }
 
+  /// Qué hacer si una señal interrumpe la espera del lock. Por defecto
+  /// devuelve el error.
+  pub fn set_eintr_policy(&mut self, policy: EintrPolicy) {
+    self.eintr = policy;
+  }
+
   /// Llamada a la función fcntl de la biblioteca libc
   fn flock(&mut self, operation: i32) -> io::Result<()> {
+    let policy = self.eintr;
+    policy.apply(|| self.fcntl_lock(operation, libc::F_SETLKW))
+  }
+
+  /// Aplica `operation` con `F_SETLKW` (bloqueante) o `F_SETLK`
+  fn fcntl_lock(&mut self, operation: i32, command: i32) -> io::Result<()> {
     let fd = self.file.as_raw_fd();
     let data = libc::flock{
       l_type: operation as i16, l_whence: libc::SEEK_SET as i16, l_start: 0, l_len: 0, l_pid: 0
     };
     let result;
     unsafe {
-      result = libc::fcntl(fd, libc::F_SETLKW, &data);
+      result = libc::fcntl(fd, command, &data);
     }
     if result == 0 {
       Ok(())
@@ -74,7 +138,14 @@ impl FileLock {
 
   /// Elimina el archivo asociado al lock.
   pub fn destroy(&mut self) -> io::Result<()> {
-    remove_file(self.path.as_str())
+    FileLock::remove(self.path.as_str())
+  }
+
+  /// Elimina el archivo de lock ubicado en `path` sin necesidad de abrirlo.
+  pub fn remove(path: &str) -> io::Result<()> {
+    remove_file(path)?;
+    registry::forget(&Resource::lock_file(path));
+    Ok(())
   }
 }
 
@@ -96,3 +167,29 @@ impl<'a> Drop for FileLockGuard<'a> {
     self.lock.unlock().unwrap();
   }
 }*/
+
+/// Abre `path` según `options`, indicando además si esta llamada creó el
+/// archivo. Para saberlo, primero se intenta crear con `O_EXCL`.
+fn open_file(path: &str, options: &IpcOptions) -> io::Result<(File, bool)> {
+  let open = |create: bool| OpenOptions::new().read(true).write(true)
+    .create_new(create)
+    .mode(options.get_mode())
+    .open(path);
+  if !options.is_create() {
+    return open(false).map(|file| (file, false));
+  }
+  loop {
+    match open(true) {
+      Ok(file) => return Ok((file, true)),
+      Err(ref e) if e.kind() == ErrorKind::AlreadyExists && !options.is_exclusive() => {
+        match open(false) {
+          Ok(file) => return Ok((file, false)),
+          // Se eliminó entre ambas llamadas: se vuelve a intentar crearlo
+          Err(ref e) if e.kind() == ErrorKind::NotFound => continue,
+          Err(e) => return Err(e)
+        }
+      },
+      Err(e) => return Err(e)
+    }
+  }
+}
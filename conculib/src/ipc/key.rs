@@ -1,10 +1,22 @@
 use libc::ftok as c_ftok;
-use libc::key_t;
-use std::io::Error;
-use std::ffi::CString;
+use libc::{key_t, IPC_PRIVATE};
+use ipc::flock::FileLock;
+use std::collections::HashMap;
+use std::fs::{create_dir_all, read_dir, remove_dir, File};
+use std::io;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::ffi::{CString, OsStr};
+use std::path::{Path, PathBuf};
+
+/// Extension of the key files created by `KeyAllocator`
+const KEY_FILE_EXTENSION: &str = "key";
+/// Lock file that protects the keys directory
+const KEYS_LOCK_FILE: &str = "keys.lock";
+/// Extension of the per-purpose directories used once every `proj_id` is taken
+const OVERFLOW_DIR_EXTENSION: &str = "keys";
 
 /// System V basic key used for IPC identification
- 
+
 pub struct Key {
   pub key: key_t
 }
@@ -34,4 +46,244 @@ impl Key {
       Err(Error::last_os_error())
     }
   }
-}
\ No newline at end of file
+
+  /// Returns the `IPC_PRIVATE` key.
+  ///
+  /// Every `get` with this key creates a new IPC, reachable only through its
+  /// id. Useful for resources created before `process::fork` and inherited
+  /// by the children.
+  pub fn private() -> Key {
+    Key{key: IPC_PRIVATE}
+  }
+
+  /// Returns `true` if this is the `IPC_PRIVATE` key.
+  pub fn is_private(&self) -> bool {
+    self.key == IPC_PRIVATE
+  }
+}
+
+/// System V key allocator
+///
+/// Creates, inside a run directory, one key file per purpose (e.g. `status`
+/// or `passenger-1234`) and assigns it a `proj_id` unique within that
+/// directory. The `proj_id` is stored in the key file itself, so every
+/// process using the same directory obtains the same key for the same purpose.
+///
+/// Once the 255 `proj_id`s of the directory are taken, each new purpose gets
+/// its own subdirectory (`<purpose>.keys`) holding a single key file.
+///
+/// On allocation the new key is checked against the keys of every other
+/// purpose, including the ones in subdirectories. If it collides, the next
+/// `proj_id` is tried; if none is left, the key file is removed and the
+/// allocation fails.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use concurrentes::ipc::KeyAllocator;
+///
+/// let mut keys = KeyAllocator::new("lake.keys").unwrap();
+/// let key = keys.allocate("status").unwrap();
+/// // Another process gets the same key
+/// let same_key = keys.lookup("status").unwrap();
+/// assert_eq!(key.key, same_key.key);
+/// keys.release("status").unwrap();
+/// ```
+pub struct KeyAllocator {
+  dir: PathBuf,
+  lock: FileLock
+}
+
+impl KeyAllocator {
+  /// Opens the keys directory `dir`, creating it if it does not exist
+  pub fn new(dir: &str) -> io::Result<KeyAllocator> {
+    create_dir_all(dir)?;
+    let dir = PathBuf::from(dir);
+    let lock_path = dir.join(KEYS_LOCK_FILE).to_string_lossy().into_owned();
+    let lock = FileLock::create(lock_path)?;
+    Ok(KeyAllocator {dir, lock})
+  }
+
+  /// Returns the key for `purpose`. If it was not allocated yet, creates its
+  /// key file with the lowest free `proj_id`.
+  pub fn allocate(&mut self, purpose: &str) -> io::Result<Key> {
+    self.lock.lock_exclusive()?;
+    let result = self.allocate_locked(purpose);
+    self.lock.unlock()?;
+    result
+  }
+
+  /// Returns the key of an already allocated purpose, without creating it
+  pub fn lookup(&mut self, purpose: &str) -> io::Result<Key> {
+    self.lock.lock_shared()?;
+    let result = self.lookup_locked(purpose);
+    self.lock.unlock()?;
+    result
+  }
+
+  /// Releases the key of `purpose`, removing its key file
+  pub fn release(&mut self, purpose: &str) -> io::Result<()> {
+    self.lock.lock_exclusive()?;
+    let result = self.release_locked(purpose);
+    self.lock.unlock()?;
+    result
+  }
+
+  /// Removes the keys directory if no keys are allocated anymore
+  pub fn destroy(&mut self) -> io::Result<()> {
+    if self.assigned()?.is_empty() && !self.has_overflow()? {
+      self.lock.destroy()?;
+      remove_dir(&self.dir)?;
+    }
+    Ok(())
+  }
+
+  /// Path of the key file for `purpose`
+  pub fn key_path(&self, purpose: &str) -> PathBuf {
+    self.dir.join(format!("{}.{}", purpose, KEY_FILE_EXTENSION))
+  }
+
+  /// Path of the key file for `purpose` inside its own subdirectory
+  fn overflow_path(&self, purpose: &str) -> PathBuf {
+    self.dir.join(format!("{}.{}", purpose, OVERFLOW_DIR_EXTENSION))
+      .join(format!("{}.{}", purpose, KEY_FILE_EXTENSION))
+  }
+
+  fn allocate_locked(&mut self, purpose: &str) -> io::Result<Key> {
+    let others: Vec<(String, Key)> = self.all_keys()?.into_iter()
+      .filter(|(other, _)| other != purpose)
+      .collect();
+    // Already allocated: checked again, since a colliding key may have been
+    // added after it
+    if let Ok(key) = self.lookup_locked(purpose) {
+      return match collision(&key, &others) {
+        Some(other) => Err(collision_error(purpose, other)),
+        None => Ok(key)
+      };
+    }
+    let assigned = self.assigned()?;
+    let free: Vec<u8> = (1..=u8::MAX)
+      .filter(|id| !assigned.values().any(|used| used == id))
+      .collect();
+    let (path, candidates) = if free.is_empty() {
+      // Every proj_id of the directory is taken
+      let overflow = self.overflow_path(purpose);
+      if let Some(dir) = overflow.parent() {
+        create_dir_all(dir)?;
+      }
+      (overflow, (1..=u8::MAX).collect())
+    } else {
+      (self.key_path(purpose), free)
+    };
+    // ftok needs the file to exist; the proj_id is written once chosen
+    let mut lock = FileLock::create(path.to_string_lossy().into_owned())?;
+    let mut last_collision = None;
+    for proj_id in candidates {
+      let key = Key::ftok(path.to_string_lossy().as_ref(), proj_id)?;
+      match collision(&key, &others) {
+        Some(other) => last_collision = Some(other.to_string()),
+        None => {
+          writeln!(lock.file, "{}", proj_id)?;
+          return Ok(key);
+        }
+      }
+    }
+    drop(lock);
+    self.remove_key_file(&path)?;
+    Err(collision_error(purpose, &last_collision.unwrap_or_default()))
+  }
+
+  fn lookup_locked(&self, purpose: &str) -> io::Result<Key> {
+    let path = self.key_path(purpose);
+    let path = if path.exists() { path } else { self.overflow_path(purpose) };
+    Key::ftok(path.to_string_lossy().as_ref(), read_proj_id(&path)?)
+  }
+
+  fn release_locked(&self, purpose: &str) -> io::Result<()> {
+    let path = self.key_path(purpose);
+    if path.exists() {
+      self.remove_key_file(&path)
+    } else {
+      self.remove_key_file(&self.overflow_path(purpose))
+    }
+  }
+
+  /// Removes a key file, and its subdirectory if it is not at the top level
+  fn remove_key_file(&self, path: &Path) -> io::Result<()> {
+    FileLock::remove(path.to_string_lossy().as_ref())?;
+    match path.parent() {
+      Some(dir) if dir != self.dir => remove_dir(dir),
+      _ => Ok(())
+    }
+  }
+
+  /// Keys of every allocated purpose, including the ones in subdirectories
+  fn all_keys(&self) -> io::Result<Vec<(String, Key)>> {
+    let mut keys = Vec::new();
+    for (purpose, proj_id) in self.assigned()? {
+      let key = self.ftok(&purpose, proj_id)?;
+      keys.push((purpose, key));
+    }
+    for entry in read_dir(&self.dir)? {
+      let path = entry?.path();
+      if !path.is_dir() || path.extension() != Some(OsStr::new(OVERFLOW_DIR_EXTENSION)) {
+        continue;
+      }
+      if let Some(purpose) = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()) {
+        let key_path = self.overflow_path(&purpose);
+        // A key file still being written has no proj_id yet
+        if let Ok(proj_id) = read_proj_id(&key_path) {
+          keys.push((purpose, Key::ftok(key_path.to_string_lossy().as_ref(), proj_id)?));
+        }
+      }
+    }
+    Ok(keys)
+  }
+
+  /// Returns `true` if some purpose has its own subdirectory
+  fn has_overflow(&self) -> io::Result<bool> {
+    for entry in read_dir(&self.dir)? {
+      let path = entry?.path();
+      if path.is_dir() && path.extension() == Some(OsStr::new(OVERFLOW_DIR_EXTENSION)) {
+        return Ok(true);
+      }
+    }
+    Ok(false)
+  }
+
+  /// Reads every purpose allocated in the directory with its `proj_id`
+  fn assigned(&self) -> io::Result<HashMap<String, u8>> {
+    let mut assigned = HashMap::new();
+    for entry in read_dir(&self.dir)? {
+      let path = entry?.path();
+      if path.extension() == Some(OsStr::new(KEY_FILE_EXTENSION)) {
+        if let (Some(purpose), Ok(proj_id)) = (path.file_stem(), read_proj_id(&path)) {
+          assigned.insert(purpose.to_string_lossy().into_owned(), proj_id);
+        }
+      }
+    }
+    Ok(assigned)
+  }
+
+  fn ftok(&self, purpose: &str, proj_id: u8) -> io::Result<Key> {
+    Key::ftok(self.key_path(purpose).to_string_lossy().as_ref(), proj_id)
+  }
+}
+
+/// Purpose in `others` whose key is equal to `key`, if any
+fn collision<'a>(key: &Key, others: &'a [(String, Key)]) -> Option<&'a str> {
+  others.iter().find(|(_, other)| other.key == key.key).map(|(other, _)| other.as_str())
+}
+
+fn collision_error(purpose: &str, other: &str) -> Error {
+  let msg = format!("Key for {} collides with key for {}", purpose, other);
+  Error::new(ErrorKind::AlreadyExists, msg)
+}
+
+/// Reads the `proj_id` stored in a key file
+fn read_proj_id(path: &Path) -> io::Result<u8> {
+  let mut content = String::new();
+  File::open(path)?.read_to_string(&mut content)?;
+  content.trim().parse::<u8>()
+    .map_err(|_| Error::new(ErrorKind::InvalidData, "Invalid key file"))
+}
@@ -9,7 +9,15 @@ pub mod semaphore;
 pub mod flock;
 /// Módulo de FIFOs
 pub mod named_pipe;
-pub use self::key::Key;
+/// Módulo de notificaciones con eventfd
+pub mod event_fd;
+/// Módulo de registro de IPCs creados
+pub mod registry;
+/// Módulo de archivos de pid de instancia única
+pub mod pid_file;
+mod options;
+pub use self::key::{Key, KeyAllocator};
+pub use self::options::IpcOptions;
 
 pub const IPC_RMID   : i32 = 0o0000;   /* remove resource */
 pub const IPC_SET    : i32 = 0o0001;   /* set ipc_perm options */
@@ -21,6 +29,7 @@ pub const IPC_EXCL   : i32 = 0o2000;   /* fail if key exists */
 pub const IPC_NOWAIT : i32 = 0o4000;   /* return error on wait */
 
 pub const SEM_UNDO     : i32 = 0x1000;
+pub const GETVAL     : i32 = 12;
 pub const SETVAL     : i32 = 16;
 
 pub const F_RDLCK: i32 = 0; /* Shared lock */
@@ -1,9 +1,14 @@
 use libc;
-use libc::{mode_t, c_int, c_void, O_WRONLY, O_RDONLY};
+use libc::{c_int, c_void, gid_t, uid_t, O_NONBLOCK, O_WRONLY, O_RDONLY};
 use std::io;
 use std::io::{Error, Write, Read};
 use std::ffi::CString;
 use std::ops::Drop;
+use std::os::unix::io::{AsRawFd, RawFd};
+use ipc::IpcOptions;
+use ipc::registry;
+use ipc::registry::Resource;
+use signal::EintrPolicy;
 
 /// Implementación de FIFOs de SystemV 
 pub struct NamedPipe {
@@ -12,7 +17,8 @@ pub struct NamedPipe {
 
 /// Implementación de wrapper de Fifo especializado en lectura
 pub struct NamedPipeReader {
-  named_pipe: NamedPipe
+  named_pipe: NamedPipe,
+  eintr: EintrPolicy
 }
 
 /// Implementación de wrapper de Fifo especializado en escritura
@@ -21,29 +27,45 @@ pub struct NamedPipeWriter {
 }
 
 impl NamedPipe {
-  /// Llama a libc `mkfifo(path, mode)`
-  /// * En caso de éxito devuelve un `NamedPipe` con su respectivo fd.
+  /// Llama a libc `mkfifo(path, mode)` y anota el FIFO en el registro de IPCs
+  /// * En caso de éxito devuelve `Ok`. Si el FIFO ya existía y no se pidió
+  ///   creación exclusiva, también devuelve `Ok`.
   /// * En caso de error devuelve el error del sistema asociado.
   ///
   /// # Example
   ///
-  /// ```rust
+  /// ```rust, no_run
+  /// use concurrentes::ipc::IpcOptions;
   /// use concurrentes::ipc::named_pipe::NamedPipe;
   ///
-  /// const NAMED_PIPE_PATH : &str = "/bin/bash";
-  /// NamedPipe::create(NAMED_PIPE_PATH, 0666);
+  /// const NAMED_PIPE_PATH : &str = "example.fifo";
+  /// NamedPipe::create(NAMED_PIPE_PATH, IpcOptions::new().create(true).mode(0o644));
   /// ```
-  pub fn create(path: &str, mode: i32) -> io::Result<()> {
+  pub fn create(path: &str, options: &IpcOptions) -> io::Result<()> {
     let path_wrapper = CString::new(path)?;
     let result;
     unsafe {
-      result = libc::mkfifo(path_wrapper.as_ptr(), mode as mode_t);
+      result = libc::mkfifo(path_wrapper.as_ptr(), options.get_mode());
     }
-    if result == 0 {
-      Ok(())
-    } else {
-      Err(Error::last_os_error())
+    if result != 0 {
+      let error = Error::last_os_error();
+      if options.is_exclusive() || error.raw_os_error() != Some(libc::EEXIST) {
+        return Err(error);
+      }
     }
+    registry::record(Resource::fifo(path));
+    if let Some((owner, group)) = options.get_ownership() {
+      let result;
+      unsafe {
+        // -1 deja el valor sin modificar
+        result = libc::chown(path_wrapper.as_ptr(),
+          owner.unwrap_or(!0 as uid_t), group.unwrap_or(!0 as gid_t));
+      }
+      if result != 0 {
+        return Err(Error::last_os_error());
+      }
+    }
+    Ok(())
   }
 
   /// Abre un FIFO ya existente
@@ -60,6 +82,25 @@ impl NamedPipe {
     }
   }
 
+  /// Activa o desactiva el modo no bloqueante del fd
+  pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+    let result;
+    unsafe {
+      let flags = libc::fcntl(self.fd, libc::F_GETFL);
+      let flags = if nonblocking {
+        flags | O_NONBLOCK
+      } else {
+        flags & !O_NONBLOCK
+      };
+      result = libc::fcntl(self.fd, libc::F_SETFL, flags);
+    }
+    if result != -1 {
+      Ok(())
+    } else {
+      Err(Error::last_os_error())
+    }
+  }
+
   /// Cierra el file descriptor asociado al FIFO
   pub fn close(&self) {
     let _result;
@@ -68,7 +109,7 @@ impl NamedPipe {
     } 
   }
 
-  /// Elimina el FIFO del sistema
+  /// Elimina el FIFO del sistema y del registro de IPCs
   pub fn unlink(path: &str) -> io::Result<()> {
     let path_wrapper = CString::new(path)?;
     let result;
@@ -76,6 +117,7 @@ impl NamedPipe {
       result = libc::unlink(path_wrapper.as_ptr());
     }
     if result == 0 {
+      registry::forget(&Resource::fifo(path));
       Ok(())
     } else {
       Err(Error::last_os_error())
@@ -116,7 +158,25 @@ impl NamedPipeReader {
   /// Abre el FIFO en sólo lectura
   pub fn open(path: &str) -> io::Result<NamedPipeReader> {
     let named_pipe = NamedPipe::open(path, O_RDONLY)?;
-    Ok(NamedPipeReader{named_pipe})
+    Ok(NamedPipeReader{named_pipe, eintr: EintrPolicy::Surface})
+  }
+
+  /// Abre el FIFO en sólo lectura sin esperar a que haya un escritor.
+  /// Las lecturas tampoco se bloquean hasta llamar a `set_nonblocking(false)`;
+  /// para esperar datos puede usarse `timer::Timer::wait_readable`.
+  pub fn open_nonblocking(path: &str) -> io::Result<NamedPipeReader> {
+    let named_pipe = NamedPipe::open(path, O_RDONLY | O_NONBLOCK)?;
+    Ok(NamedPipeReader{named_pipe, eintr: EintrPolicy::Surface})
+  }
+
+  /// Activa o desactiva el modo no bloqueante de las lecturas
+  pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+    self.named_pipe.set_nonblocking(nonblocking)
+  }
+
+  /// Qué hacer si una señal interrumpe `read`. Por defecto devuelve el error.
+  pub fn set_eintr_policy(&mut self, policy: EintrPolicy) {
+    self.eintr = policy;
   }
 }
 
@@ -124,15 +184,24 @@ impl Read for NamedPipeReader {
   /// Utiliza la primitiva de libc `read` para leer contenidos del pipe al buffer
   fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
     let buf_pointer = &mut buf[0] as *mut u8 as *mut c_void;
-    let result;
-    unsafe {
-      result = libc::read(self.named_pipe.fd, buf_pointer, buf.len());
-    }
-    if result >= 0 {
-      Ok(result as usize)
-    } else {
-      Err(Error::last_os_error())
-    }
+    let fd = self.named_pipe.fd;
+    self.eintr.apply(|| {
+      let result;
+      unsafe {
+        result = libc::read(fd, buf_pointer, buf.len());
+      }
+      if result >= 0 {
+        Ok(result as usize)
+      } else {
+        Err(Error::last_os_error())
+      }
+    })
+  }
+}
+
+impl AsRawFd for NamedPipeReader {
+  fn as_raw_fd(&self) -> RawFd {
+    self.named_pipe.fd
   }
 }
 
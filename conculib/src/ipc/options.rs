@@ -0,0 +1,122 @@
+use libc::{gid_t, ipc_perm, mode_t, uid_t};
+use ipc::{IPC_CREAT, IPC_EXCL};
+
+/// Opciones de creación y permisos de los IPCs
+///
+/// Reemplaza a los flags crudos (`IPC_CREAT | IPC_EXCL | 0o660`) que reciben
+/// los constructores de semáforos, memoria compartida, FIFOs y FileLocks. Se
+/// usa de forma similar a `std::fs::OpenOptions`.
+///
+/// Por defecto abre un IPC existente, con permisos `0o660` en caso de crearlo.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use concurrentes::ipc::{IpcOptions, Key};
+/// use concurrentes::ipc::semaphore::Semaphore;
+///
+/// let key = Key::ftok("/bin/bash", 0).unwrap();
+/// // Crea el semáforo, fallando si ya existe
+/// let sem = Semaphore::get(&key, IpcOptions::new().create(true).exclusive(true)).unwrap();
+/// // Abre el semáforo ya existente
+/// let same_sem = Semaphore::get(&key, &IpcOptions::new()).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct IpcOptions {
+  create: bool,
+  exclusive: bool,
+  mode: mode_t,
+  owner: Option<uid_t>,
+  group: Option<gid_t>
+}
+
+impl IpcOptions {
+  /// Opciones para abrir un IPC existente
+  pub fn new() -> IpcOptions {
+    IpcOptions {create: false, exclusive: false, mode: 0o660, owner: None, group: None}
+  }
+
+  /// Crea el IPC si no existe
+  pub fn create(&mut self, create: bool) -> &mut IpcOptions {
+    self.create = create;
+    self
+  }
+
+  /// Junto con `create`, falla si el IPC ya existe
+  pub fn exclusive(&mut self, exclusive: bool) -> &mut IpcOptions {
+    self.exclusive = exclusive;
+    self
+  }
+
+  /// Permisos con los que se crea el IPC (por ejemplo `0o660`)
+  pub fn mode(&mut self, mode: mode_t) -> &mut IpcOptions {
+    self.mode = mode;
+    self
+  }
+
+  /// Usuario dueño del IPC creado
+  pub fn owner(&mut self, owner: uid_t) -> &mut IpcOptions {
+    self.owner = Some(owner);
+    self
+  }
+
+  /// Grupo dueño del IPC creado
+  pub fn group(&mut self, group: gid_t) -> &mut IpcOptions {
+    self.group = Some(group);
+    self
+  }
+
+  /// Indica si se pidió la creación del IPC
+  pub fn is_create(&self) -> bool {
+    self.create
+  }
+
+  /// Indica si se pidió la creación exclusiva del IPC
+  pub fn is_exclusive(&self) -> bool {
+    self.create && self.exclusive
+  }
+
+  /// Permisos con los que se crea el IPC
+  pub fn get_mode(&self) -> mode_t {
+    self.mode
+  }
+
+  /// Usuario y grupo pedidos, si alguno fue asignado
+  pub fn get_ownership(&self) -> Option<(Option<uid_t>, Option<gid_t>)> {
+    if self.owner.is_some() || self.group.is_some() {
+      Some((self.owner, self.group))
+    } else {
+      None
+    }
+  }
+
+  /// Flags para `semget` / `shmget`
+  pub fn flags(&self) -> i32 {
+    let mut flags = self.mode as i32;
+    if self.create {
+      flags |= IPC_CREAT;
+      if self.exclusive {
+        flags |= IPC_EXCL;
+      }
+    }
+    flags
+  }
+
+  /// Aplica dueño, grupo y permisos a la estructura `ipc_perm` de un IPC de
+  /// System V, para luego guardarla con `IPC_SET`
+  pub fn apply_perm(&self, perm: &mut ipc_perm) {
+    if let Some(owner) = self.owner {
+      perm.uid = owner;
+    }
+    if let Some(group) = self.group {
+      perm.gid = group;
+    }
+    perm.mode = self.mode as _;
+  }
+}
+
+impl Default for IpcOptions {
+  fn default() -> IpcOptions {
+    IpcOptions::new()
+  }
+}
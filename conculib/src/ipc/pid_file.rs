@@ -0,0 +1,63 @@
+use ipc::flock::FileLock;
+use libc::pid_t;
+
+use std::fs::File;
+use std::io;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::process;
+
+/// Archivo de pid de instancia única
+///
+/// Garantiza que un único proceso a la vez tenga el archivo: al adquirirlo
+/// toma un lock exclusivo (sin bloquearse) y escribe su pid. El lock de
+/// `fcntl` se libera solo si el proceso muere, por lo que un archivo que
+/// quedó de una corrida abortada puede volver a adquirirse.
+///
+/// **Importante**: los locks de `fcntl` no se heredan con `fork` y se
+/// pierden si el proceso cierra cualquier otro descriptor del mismo archivo.
+/// Si se va a usar `process::daemonize`, el archivo debe adquirirse después.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use concurrentes::ipc::pid_file::PidFile;
+///
+/// let pid_file = PidFile::acquire("app.pid").unwrap();
+/// // Otro proceso que intente adquirirlo recibe un error AlreadyExists
+/// ```
+pub struct PidFile {
+  lock: FileLock
+}
+
+impl PidFile {
+  /// Adquiere el archivo de pid `path`, creándolo si no existe. Si otro
+  /// proceso lo tiene, falla con `ErrorKind::AlreadyExists`.
+  pub fn acquire(path: &str) -> io::Result<PidFile> {
+    let mut lock = FileLock::create(path.to_string())?;
+    if !lock.try_lock_exclusive()? {
+      let msg = match PidFile::read_pid(path) {
+        Ok(pid) => format!("{} pertenece al proceso {}", path, pid),
+        Err(_) => format!("{} pertenece a otro proceso", path)
+      };
+      return Err(Error::new(ErrorKind::AlreadyExists, msg));
+    }
+    lock.file.set_len(0)?;
+    writeln!(lock.file, "{}", process::id())?;
+    Ok(PidFile {lock})
+  }
+
+  /// Lee el pid guardado en el archivo `path`
+  pub fn read_pid(path: &str) -> io::Result<pid_t> {
+    let mut content = String::new();
+    File::open(path)?.read_to_string(&mut content)?;
+    content.trim().parse::<pid_t>()
+      .map_err(|_| Error::new(ErrorKind::InvalidData, "Archivo de pid inválido"))
+  }
+}
+
+impl Drop for PidFile {
+  /// Elimina el archivo, liberando el lock
+  fn drop(&mut self) {
+    let _ = self.lock.destroy();
+  }
+}
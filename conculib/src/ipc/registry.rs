@@ -0,0 +1,285 @@
+use libc;
+use libc::{key_t, pid_t};
+use ipc::{IPC_RMID, IPC_STAT};
+use ipc::flock::FileLock;
+
+use std::env;
+use std::fmt;
+use std::fs::{OpenOptions, remove_file};
+use std::io;
+use std::io::{BufRead, BufReader, Error, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::mem;
+use std::process;
+use std::ptr;
+
+/// Manifiesto utilizado si no se define `MANIFEST_ENV`
+pub const DEFAULT_MANIFEST: &str = "concu-ipcs.manifest";
+/// Variable de entorno para cambiar la ruta del manifiesto
+pub const MANIFEST_ENV: &str = "CONCU_IPCS_MANIFEST";
+/// Variable de entorno con el pid al que se atribuyen los IPCs de la
+/// corrida. Si no se define, se atribuyen al proceso que los crea.
+pub const OWNER_ENV: &str = "CONCU_IPCS_OWNER";
+
+/// IPC creado por la biblioteca
+///
+/// Los semáforos y memorias compartidas se identifican por su id, junto a
+/// la clave con que se crearon para no confundirlos con otro IPC que reciba
+/// el mismo id. Los FIFOs y archivos de lock se identifican por su ruta
+/// absoluta.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Resource {
+  Semaphore(i32, key_t),
+  Shmem(i32, key_t),
+  Fifo(String),
+  LockFile(String)
+}
+
+/// Entrada del manifiesto: un recurso y el pid de la corrida que lo creó
+#[derive(Clone, Debug)]
+pub struct Entry {
+  pub owner: pid_t,
+  pub resource: Resource
+}
+
+/// Registro de IPCs creados durante una corrida
+///
+/// Cada recurso creado se anota en un archivo de manifiesto, una línea por
+/// recurso con el formato `<pid> <tipo> <id y clave, o ruta>`. El manifiesto
+/// se protege con un `FileLock` para que varios procesos puedan escribirlo.
+///
+/// Los recursos se atribuyen al pid indicado en `OWNER_ENV`, que el proceso
+/// principal de la corrida define para sus hijos; así sus recursos se
+/// conservan mientras él siga vivo, aunque termine el hijo que los creó.
+///
+/// Si un proceso termina abruptamente, el manifiesto permite a `concu-ipcs`
+/// encontrar y eliminar los recursos que quedaron en el sistema.
+pub struct Registry {
+  lock: FileLock
+}
+
+impl Resource {
+  /// Recurso correspondiente a un FIFO en `path`
+  pub fn fifo(path: &str) -> Resource {
+    Resource::Fifo(absolute_path(path))
+  }
+
+  /// Recurso correspondiente a un archivo de lock en `path`
+  pub fn lock_file(path: &str) -> Resource {
+    Resource::LockFile(absolute_path(path))
+  }
+
+  /// Indica si ambos recursos corresponden al mismo IPC. Los recursos
+  /// basados en archivos se comparan únicamente por su ruta.
+  pub fn same_as(&self, other: &Resource) -> bool {
+    match (self.path(), other.path()) {
+      (Some(path), Some(other_path)) => path == other_path,
+      _ => self == other
+    }
+  }
+
+  /// Ruta del recurso, si está basado en un archivo
+  pub fn path(&self) -> Option<&str> {
+    match *self {
+      Resource::Fifo(ref path) | Resource::LockFile(ref path) => Some(path.as_str()),
+      _ => None
+    }
+  }
+
+  /// Indica si el recurso sigue existiendo en el sistema. Un semáforo o
+  /// memoria compartida cuyo id tiene ahora otra clave se considera
+  /// eliminado, ya que el kernel reutilizó el id para otro IPC.
+  pub fn exists(&self) -> bool {
+    match *self {
+      Resource::Semaphore(id, key) => unsafe {
+        let mut buf: libc::semid_ds = mem::zeroed();
+        libc::semctl(id, 0, IPC_STAT, &mut buf as *mut libc::semid_ds) != -1
+          && buf.sem_perm.__key == key
+      },
+      Resource::Shmem(id, key) => unsafe {
+        let mut buf: libc::shmid_ds = mem::zeroed();
+        libc::shmctl(id, IPC_STAT, &mut buf) != -1 && buf.shm_perm.__key == key
+      },
+      Resource::Fifo(ref path) | Resource::LockFile(ref path) => Path::new(path).exists()
+    }
+  }
+
+  /// Elimina el recurso del sistema. Falla con `NotFound` si el id de un
+  /// semáforo o memoria compartida ya corresponde a otro IPC.
+  pub fn remove(&self) -> io::Result<()> {
+    let result = match *self {
+      Resource::Semaphore(..) | Resource::Shmem(..) if !self.exists() => {
+        let msg = format!("{} ya no existe o su id corresponde a otro IPC", self);
+        return Err(Error::new(io::ErrorKind::NotFound, msg));
+      },
+      Resource::Semaphore(id, _) => unsafe { libc::semctl(id, 0, IPC_RMID) },
+      Resource::Shmem(id, _) => unsafe { libc::shmctl(id, IPC_RMID, ptr::null_mut()) },
+      Resource::Fifo(ref path) | Resource::LockFile(ref path) => return remove_file(path)
+    };
+    if result != -1 {
+      Ok(())
+    } else {
+      Err(Error::last_os_error())
+    }
+  }
+
+  /// Interpreta el tipo y el identificador de una línea del manifiesto
+  fn parse(kind: &str, ident: &str) -> Option<Resource> {
+    let id_and_key = || {
+      let mut fields = ident.split(' ');
+      let id = fields.next()?.parse().ok()?;
+      let key = fields.next()?.parse().ok()?;
+      Some((id, key))
+    };
+    match kind {
+      "sem" => id_and_key().map(|(id, key)| Resource::Semaphore(id, key)),
+      "shm" => id_and_key().map(|(id, key)| Resource::Shmem(id, key)),
+      "fifo" => Some(Resource::Fifo(ident.to_string())),
+      "lock" => Some(Resource::LockFile(ident.to_string())),
+      _ => None
+    }
+  }
+}
+
+impl fmt::Display for Resource {
+  /// Formato utilizado en el manifiesto: `<tipo> <id y clave, o ruta>`
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Resource::Semaphore(id, key) => write!(f, "sem {} {}", id, key),
+      Resource::Shmem(id, key) => write!(f, "shm {} {}", id, key),
+      Resource::Fifo(ref path) => write!(f, "fifo {}", path),
+      Resource::LockFile(ref path) => write!(f, "lock {}", path)
+    }
+  }
+}
+
+impl Entry {
+  /// Indica si el proceso al que se atribuye el recurso sigue vivo
+  pub fn is_owner_alive(&self) -> bool {
+    let result = unsafe { libc::kill(self.owner, 0) };
+    result == 0 || Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+  }
+
+  /// Interpreta una línea del manifiesto
+  fn parse(line: &str) -> Option<Entry> {
+    let mut fields = line.splitn(3, ' ');
+    let owner = fields.next()?.parse().ok()?;
+    let resource = Resource::parse(fields.next()?, fields.next()?)?;
+    Some(Entry {owner, resource})
+  }
+}
+
+impl Registry {
+  /// Abre (o crea) el manifiesto ubicado en `path`
+  pub fn open(path: &str) -> io::Result<Registry> {
+    let mut options = OpenOptions::new();
+    options.read(true).write(true).create(true);
+    // No se usa FileLock::create para que el manifiesto no se registre a sí mismo
+    let lock = FileLock::new_with_options(path.to_string(), &options)?;
+    Ok(Registry {lock})
+  }
+
+  /// Abre el manifiesto de la corrida actual: el indicado en la variable de
+  /// entorno `CONCU_IPCS_MANIFEST` o, si no existe, `concu-ipcs.manifest`
+  pub fn open_default() -> io::Result<Registry> {
+    let path = env::var(MANIFEST_ENV).unwrap_or_else(|_| DEFAULT_MANIFEST.to_string());
+    Registry::open(path.as_str())
+  }
+
+  /// Anota un recurso creado por el proceso actual, atribuyéndolo a
+  /// `owner()`. Si el recurso ya estaba anotado por un proceso que sigue
+  /// vivo, no se modifica el manifiesto.
+  pub fn record(&mut self, resource: Resource) -> io::Result<()> {
+    self.lock.lock_exclusive()?;
+    let result = self.read_entries().and_then(|mut entries| {
+      if let Some(entry) = entries.iter().find(|e| e.resource.same_as(&resource)) {
+        if entry.is_owner_alive() {
+          return Ok(());
+        }
+      }
+      entries.retain(|e| !e.resource.same_as(&resource));
+      entries.push(Entry {owner: owner(), resource});
+      self.write_entries(&entries)
+    });
+    self.lock.unlock()?;
+    result
+  }
+
+  /// Quita un recurso del manifiesto, una vez eliminado del sistema
+  pub fn forget(&mut self, resource: &Resource) -> io::Result<()> {
+    self.lock.lock_exclusive()?;
+    let result = self.read_entries().and_then(|mut entries| {
+      let len = entries.len();
+      entries.retain(|e| !e.resource.same_as(resource));
+      if entries.len() != len {
+        self.write_entries(&entries)
+      } else {
+        Ok(())
+      }
+    });
+    self.lock.unlock()?;
+    result
+  }
+
+  /// Devuelve todas las entradas del manifiesto
+  pub fn entries(&mut self) -> io::Result<Vec<Entry>> {
+    self.lock.lock_shared()?;
+    let result = self.read_entries();
+    self.lock.unlock()?;
+    result
+  }
+
+  fn read_entries(&mut self) -> io::Result<Vec<Entry>> {
+    self.lock.file.seek(SeekFrom::Start(0))?;
+    let reader = BufReader::new(&self.lock.file);
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+      if let Some(entry) = Entry::parse(line?.as_str()) {
+        entries.push(entry);
+      }
+    }
+    Ok(entries)
+  }
+
+  fn write_entries(&mut self, entries: &[Entry]) -> io::Result<()> {
+    let mut data = String::new();
+    for entry in entries {
+      data.push_str(format!("{} {}\n", entry.owner, entry.resource).as_str());
+    }
+    self.lock.file.set_len(0)?;
+    self.lock.file.seek(SeekFrom::Start(0))?;
+    self.lock.file.write_all(data.as_bytes())
+  }
+}
+
+/// Anota el recurso en el manifiesto de la corrida actual.
+///
+/// El registro es de mejor esfuerzo: si el manifiesto no puede abrirse, el
+/// error se descarta para no impedir la creación del IPC.
+pub fn record(resource: Resource) {
+  if let Ok(mut registry) = Registry::open_default() {
+    let _ = registry.record(resource);
+  }
+}
+
+/// Quita el recurso del manifiesto de la corrida actual (mejor esfuerzo)
+pub fn forget(resource: &Resource) {
+  if let Ok(mut registry) = Registry::open_default() {
+    let _ = registry.forget(resource);
+  }
+}
+
+/// Pid al que se atribuyen los recursos creados por el proceso actual: el
+/// definido en `OWNER_ENV` o, si no existe, el propio
+pub fn owner() -> pid_t {
+  env::var(OWNER_ENV).ok().and_then(|pid| pid.parse().ok())
+    .unwrap_or(process::id() as pid_t)
+}
+
+/// Convierte `path` en una ruta absoluta respecto del directorio actual
+fn absolute_path(path: &str) -> String {
+  match env::current_dir() {
+    Ok(dir) => dir.join(path).to_string_lossy().into_owned(),
+    Err(_) => path.to_string()
+  }
+}
@@ -1,23 +1,56 @@
 use libc;
 use libc::sembuf;
 use std::io;
-use ipc::{IPC_RMID, SETVAL, SEM_UNDO};
+use std::mem;
+use ipc::{IPC_RMID, IPC_SET, IPC_STAT, GETVAL, SETVAL, SEM_UNDO, IpcOptions};
 use ipc::key::Key;
+use ipc::registry;
+use ipc::registry::Resource;
+use signal::EintrPolicy;
 
 /// Wrapper para semáforo SystemV
 pub struct Semaphore {
   id: i32,
+  key: libc::key_t,
+  undo: bool,
+  eintr: EintrPolicy
 }
 
 impl Semaphore {
-  /// Obtiene un semáforo (array de tamaño 1) según la clave asignada
-  pub fn get(key: &Key, flags: i32) -> io::Result<Semaphore> {
+  /// Obtiene un semáforo (array de tamaño 1) según la clave asignada.
+  /// Si se pide su creación, lo anota en el registro de IPCs y le asigna el
+  /// dueño y grupo indicados en `options`.
+  pub fn get(key: &Key, options: &IpcOptions) -> io::Result<Semaphore> {
     let id;
     unsafe {
-      id = libc::semget(key.key, 1, flags);
+      id = libc::semget(key.key, 1, options.flags());
     }
-    if id != -1 {
-      Ok(Semaphore{id})
+    if id == -1 {
+      return Err(io::Error::last_os_error());
+    }
+    let semaphore = Semaphore{id, key: key.key, undo: true, eintr: EintrPolicy::Surface};
+    if options.is_create() {
+      registry::record(Resource::Semaphore(id, key.key));
+      if options.get_ownership().is_some() {
+        semaphore.set_permissions(options)?;
+      }
+    }
+    Ok(semaphore)
+  }
+
+  /// Aplica dueño, grupo y permisos de `options` mediante `IPC_SET`
+  pub fn set_permissions(&self, options: &IpcOptions) -> io::Result<()> {
+    let mut result;
+    unsafe {
+      let mut buf: libc::semid_ds = mem::zeroed();
+      result = libc::semctl(self.id, 0, IPC_STAT, &mut buf as *mut libc::semid_ds);
+      if result != -1 {
+        options.apply_perm(&mut buf.sem_perm);
+        result = libc::semctl(self.id, 0, IPC_SET, &mut buf as *mut libc::semid_ds);
+      }
+    }
+    if result != -1 {
+      Ok(())
     } else {
       Err(io::Error::last_os_error())
     }
@@ -25,14 +58,9 @@ impl Semaphore {
 
   /// Inicializa un semáforo en el valor pasado por parámetro.
   pub fn init(&self, init_value: i32) -> io::Result<()> {
-    let buf = sembuf {
-      sem_num: 0,
-      sem_op: init_value as libc::c_short,
-      sem_flg: 0
-    };
     let result;
     unsafe {
-      result = libc::semctl(self.id, 0, SETVAL, buf);
+      result = libc::semctl(self.id, 0, SETVAL, init_value as libc::c_int);
     }
     if result != -1 {
       Ok(())
@@ -41,6 +69,19 @@ impl Semaphore {
     }
   }
 
+  /// Obtiene el valor actual del semáforo
+  pub fn get_value(&self) -> io::Result<i32> {
+    let result;
+    unsafe {
+      result = libc::semctl(self.id, 0, GETVAL);
+    }
+    if result != -1 {
+      Ok(result)
+    } else {
+      Err(io::Error::last_os_error())
+    }
+  }
+
   /// Resta uno al valor del semáforo, y se bloquea si este queda en negativo
   pub fn wait(&self) -> io::Result<()> {
     unsafe {
@@ -55,19 +96,34 @@ impl Semaphore {
     }
   }
 
+  /// Indica si `wait` y `signal` usan `SEM_UNDO`, para que el kernel revierta
+  /// sus operaciones cuando el proceso termina. Por defecto sí; debe
+  /// desactivarse si un proceso incrementa el semáforo y otro lo decrementa,
+  /// ya que al terminar el primero se desharía el incremento.
+  pub fn set_undo(&mut self, undo: bool) {
+    self.undo = undo;
+  }
+
+  /// Qué hacer si una señal interrumpe `wait`. Por defecto devuelve el error.
+  pub fn set_eintr_policy(&mut self, policy: EintrPolicy) {
+    self.eintr = policy;
+  }
+
   /// Llamada a semop para realizar operaciones sobre el semáforo de forma nativa
   unsafe fn modify(&self, value: i32) -> io::Result<()> {
-    let mut buf = sembuf {
-      sem_num: 0,
-      sem_op: value as libc::c_short,
-      sem_flg: SEM_UNDO as i16
-    };
-    let result = libc::semop(self.id, &mut buf, 1);
-    if result != -1 {
-      Ok(())
-    } else {
-      Err(io::Error::last_os_error())
-    }
+    self.eintr.apply(|| {
+      let mut buf = sembuf {
+        sem_num: 0,
+        sem_op: value as libc::c_short,
+        sem_flg: if self.undo { SEM_UNDO as i16 } else { 0 }
+      };
+      let result = libc::semop(self.id, &mut buf, 1);
+      if result != -1 {
+        Ok(())
+      } else {
+        Err(io::Error::last_os_error())
+      }
+    })
   }
 
   /// Elimina el IPC del sistema
@@ -75,6 +131,7 @@ impl Semaphore {
     unsafe {
       libc::semctl(self.id, 0, IPC_RMID);
     }
+    registry::forget(&Resource::Semaphore(self.id, self.key));
   }
 }
 
@@ -3,9 +3,11 @@ use libc::shmget as c_shmget;
 use libc::shmctl as c_shmctl;
 use libc::shmat as c_shmat;
 use libc::shmdt as c_shmdt;
-use libc::shmid_ds;
-use ipc::IPC_RMID;
+use libc::{key_t, shmid_ds};
+use ipc::{IPC_RMID, IPC_SET, IPC_STAT, IpcOptions};
 use ipc::key::Key;
+use ipc::registry;
+use ipc::registry::Resource;
 use std::io::Error;
 use std::mem;
 use std::ptr;
@@ -18,21 +20,40 @@ use std::cell::RefCell;
 /// reservar un buffer del tamaño de `num` unidades de `<T>`
 pub struct Shmem<T> {
   id: i32,
+  key: key_t,
   num: usize,
   data: *mut T
 }
 
 impl <T> Shmem<T> {
-  /// Obtiene un id de memoria compartida correspondiente a la clave `key`
-  pub fn get(key: &Key, num: usize, flags: i32) -> Result<Shmem<T>, Error> {
+  /// Obtiene un id de memoria compartida correspondiente a la clave `key`.
+  /// Si se pide su creación, la anota en el registro de IPCs y le asigna el
+  /// dueño y grupo indicados en `options`.
+  pub fn get(key: &Key, num: usize, options: &IpcOptions) -> Result<Shmem<T>, Error> {
     let id;
     unsafe {
-      id = c_shmget(key.key, mem::size_of::<T>() * num, flags);
+      id = c_shmget(key.key, mem::size_of::<T>() * num, options.flags());
     }
-    if id != -1 {
-      Ok(Shmem{id, data: ptr::null_mut(), num})
-    } else {
-      Err(Error::last_os_error())
+    if id == -1 {
+      return Err(Error::last_os_error());
+    }
+    let shmem = Shmem{id, key: key.key, data: ptr::null_mut(), num};
+    if options.is_create() {
+      registry::record(Resource::Shmem(id, key.key));
+      if options.get_ownership().is_some() {
+        shmem.set_permissions(options)?;
+      }
+    }
+    Ok(shmem)
+  }
+
+  /// Aplica dueño, grupo y permisos de `options` mediante `IPC_SET`
+  pub fn set_permissions(&self, options: &IpcOptions) -> Result<(), Error> {
+    unsafe {
+      let mut buf: shmid_ds = mem::zeroed();
+      self.control(IPC_STAT, &mut buf)?;
+      options.apply_perm(&mut buf.shm_perm);
+      self.control(IPC_SET, &mut buf)
     }
   }
 
@@ -75,8 +96,10 @@ impl <T> Shmem<T> {
   /// Elimina el IPC de memoria compartida
   pub fn destroy(&self) -> Result<(), Error> {
     unsafe {
-      self.control(IPC_RMID, ptr::null_mut())
+      self.control(IPC_RMID, ptr::null_mut())?;
     }
+    registry::forget(&Resource::Shmem(self.id, self.key));
+    Ok(())
   }
 
   /// Obtiene una copia del dato al que apunta la memoria compartida
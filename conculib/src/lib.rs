@@ -9,6 +9,7 @@ extern crate chrono;
 /// * Memoria compartida
 /// * Semaforos
 /// * FIFOs (NamedPipes)
+/// * EventFds
 ///
 /// También posee varias constantes necesarias para interactuar con las primitivas de libc
 pub mod ipc;
@@ -18,4 +19,17 @@ pub mod process;
 pub mod signal;
 /// Contiene un log que utiliza un FileLock para poder ser usado por distintos procesos. También
 /// posee un macro para facilitar el formato del log.
+#[macro_use]
 pub mod log;
+/// Contiene patrones de sincronización clásicos construidos sobre semáforos:
+///
+/// * Molinete (Turnstile)
+/// * Interruptor de luz (Lightswitch)
+/// * Multiplex
+/// * Punto de encuentro (Rendezvous)
+/// * Barrera reutilizable
+pub mod patterns;
+/// Contiene un supervisor que relanza procesos según una política de reinicio
+pub mod supervisor;
+/// Contiene temporizadores de alta resolución basados en `timerfd`
+pub mod timer;
@@ -2,17 +2,43 @@ use super::ipc::flock::FileLock;
 
 use chrono::Local;
 
+use std::env;
+use std::fs;
 use std::fs::OpenOptions;
 use std::io;
 use std::process;
 use std::io::{Write};
-use std::cell::RefCell;
+use std::os::unix::fs::MetadataExt;
 use std::str::FromStr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Archivo de log por defecto, en el directorio actual
+pub const DEFAULT_LOG_PATH: &str = "tp.log";
+/// Variable de entorno que, si está definida, reemplaza la ruta del log
+pub const LOG_PATH_VAR: &str = "CONCU_LOG_PATH";
+/// Variable de entorno que, si está definida, reemplaza la severidad mínima
+pub const LOG_LEVEL_VAR: &str = "CONCU_LOG_LEVEL";
+/// Variable de entorno que, si está definida, reemplaza el formato (`text` o `json`)
+pub const LOG_FORMAT_VAR: &str = "CONCU_LOG_FORMAT";
+/// Variable de entorno que, si está definida, reemplaza el tamaño máximo del
+/// log en bytes antes de rotarlo
+pub const LOG_MAX_BYTES_VAR: &str = "CONCU_LOG_MAX_BYTES";
+/// Variable de entorno que, si está definida, reemplaza el intervalo de
+/// rotación del log en segundos
+pub const LOG_ROTATE_SECS_VAR: &str = "CONCU_LOG_ROTATE_SECS";
+/// Variable de entorno que, si está definida, reemplaza la cantidad de logs
+/// rotados que se conservan
+pub const LOG_KEEP_VAR: &str = "CONCU_LOG_KEEP";
+/// Evento de los mensajes logueados sin nombre de evento
+pub const DEFAULT_EVENT: &str = "log";
 
 /// Severidad del mensaje
 ///
-/// Permite 4 niveles de severidad standard: `Error`, `WARN`, `INFO`, `DEBUG`
-#[derive(Debug)]
+/// Permite 4 niveles de severidad standard: `Error`, `WARN`, `INFO`, `DEBUG`.
+/// Están ordenadas de la más a la menos grave: `ERROR < DEBUG`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum LogSeverity {
   ERROR,
   WARN,
@@ -20,30 +46,289 @@ pub enum LogSeverity {
   DEBUG
 }
 
+impl FromStr for LogSeverity {
+  type Err = String;
+
+  /// Interpreta el nombre de una severidad, sin distinguir mayúsculas
+  fn from_str(name: &str) -> Result<LogSeverity, String> {
+    match name.trim().to_uppercase().as_str() {
+      "ERROR" => Ok(LogSeverity::ERROR),
+      "WARN" => Ok(LogSeverity::WARN),
+      "INFO" => Ok(LogSeverity::INFO),
+      "DEBUG" => Ok(LogSeverity::DEBUG),
+      _ => Err(format!("Severidad de log desconocida: {}", name))
+    }
+  }
+}
+
+/// Formato de las líneas del log
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+  /// `<fecha> [<pid>] [<severidad>] - mensaje`, pensado para leerse con multitail
+  Text,
+  /// Un objeto JSON por línea (JSON lines), pensado para procesarse con otras
+  /// herramientas. Incluye el tipo de entidad, el evento y los campos del registro.
+  Json
+}
+
+impl FromStr for LogFormat {
+  type Err = String;
+
+  /// Interpreta el nombre de un formato (`text` o `json`), sin distinguir mayúsculas
+  fn from_str(name: &str) -> Result<LogFormat, String> {
+    match name.trim().to_lowercase().as_str() {
+      "text" => Ok(LogFormat::Text),
+      "json" => Ok(LogFormat::Json),
+      _ => Err(format!("Formato de log desconocido: {}", name))
+    }
+  }
+}
+
+/// Valor de un campo de un registro estructurado
+#[derive(Clone, Debug, PartialEq)]
+pub enum LogValue {
+  Int(i64),
+  Str(String)
+}
+
+impl LogValue {
+  /// Representación JSON del valor
+  fn to_json(&self) -> String {
+    match *self {
+      LogValue::Int(value) => value.to_string(),
+      LogValue::Str(ref value) => json_string(value)
+    }
+  }
+}
+
+impl From<i32> for LogValue {
+  fn from(value: i32) -> LogValue {
+    LogValue::Int(i64::from(value))
+  }
+}
+
+impl From<u32> for LogValue {
+  fn from(value: u32) -> LogValue {
+    LogValue::Int(i64::from(value))
+  }
+}
+
+impl From<i64> for LogValue {
+  fn from(value: i64) -> LogValue {
+    LogValue::Int(value)
+  }
+}
+
+impl<'a> From<&'a str> for LogValue {
+  fn from(value: &'a str) -> LogValue {
+    LogValue::Str(value.to_string())
+  }
+}
+
+impl From<String> for LogValue {
+  fn from(value: String) -> LogValue {
+    LogValue::Str(value)
+  }
+}
+
+/// Política de rotación del log: tamaño máximo, intervalo y cantidad de
+/// archivos rotados a conservar.
+///
+/// El log se rota cuando alcanza `max_bytes`, o cuando su última escritura
+/// quedó en un intervalo `interval` anterior al actual (los intervalos se
+/// cuentan desde la época Unix, por lo que todos los procesos coinciden).
+/// Al rotar, `tp.log` pasa a ser `tp.log.1`, `tp.log.1` pasa a ser
+/// `tp.log.2`, y así hasta conservar `keep` archivos rotados.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use concurrentes::log::{Log, LogRotation};
+/// use std::time::Duration;
+///
+/// Log::init_rotation(LogRotation::new().max_bytes(1 << 20).interval(Duration::from_secs(3600)))
+///   .unwrap();
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LogRotation {
+  max_bytes: Option<u64>,
+  interval: Option<Duration>,
+  keep: usize
+}
+
+impl Default for LogRotation {
+  fn default() -> LogRotation {
+    LogRotation::new()
+  }
+}
+
+impl LogRotation {
+  /// Sin rotación; si se habilita, conserva 5 archivos rotados
+  pub fn new() -> LogRotation {
+    LogRotation {max_bytes: None, interval: None, keep: 5}
+  }
+
+  /// Rota el log al alcanzar `bytes` bytes
+  pub fn max_bytes(&mut self, bytes: u64) -> &mut LogRotation {
+    self.max_bytes = Some(bytes);
+    self
+  }
+
+  /// Rota el log al comenzar cada intervalo `interval` (como mínimo, un segundo)
+  pub fn interval(&mut self, interval: Duration) -> &mut LogRotation {
+    self.interval = Some(interval);
+    self
+  }
+
+  /// Cantidad de archivos rotados que se conservan (como mínimo, uno)
+  pub fn keep(&mut self, files: usize) -> &mut LogRotation {
+    self.keep = files.max(1);
+    self
+  }
+
+  /// Indica si hay que rotar un log con los metadatos `metadata`
+  fn should_rotate(&self, metadata: &fs::Metadata) -> bool {
+    if metadata.len() == 0 {
+      return false;
+    }
+    if self.max_bytes.is_some_and(|max_bytes| metadata.len() >= max_bytes) {
+      return true;
+    }
+    match (self.interval, metadata.modified()) {
+      (Some(interval), Ok(modified)) => {
+        let secs = interval.as_secs().max(1);
+        period(modified, secs) != period(SystemTime::now(), secs)
+      },
+      _ => false
+    }
+  }
+}
+
+/// Número de intervalo de `secs` segundos al que pertenece `time`
+fn period(time: SystemTime, secs: u64) -> u64 {
+  time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs() / secs).unwrap_or(0)
+}
+
 /// Log
 ///
-/// Posee un FileLock para sincronizar la escritura entre los distintos procesos
+/// Posee un FileLock para sincronizar la escritura entre los distintos procesos.
+/// Descarta los mensajes menos graves que su severidad mínima.
+///
+/// Si tiene una política de rotación, el proceso que escribe cuando se
+/// cumple la condición rota el log mientras tiene tomado el lock. Antes de
+/// escribir, cada proceso verifica que su archivo siga siendo el de la ruta
+/// del log (comparando inodos); si otro lo rotó, lo reabre. Así ninguna
+/// línea se pierde, se duplica ni queda en un archivo ya rotado.
 pub struct Log {
-  file_lock: FileLock
+  file_lock: FileLock,
+  min_severity: LogSeverity,
+  format: LogFormat,
+  rotation: LogRotation
 }
 
-thread_local! {
-  /// Instancia única del log accesible en toda la aplicación
-  pub static GLOBAL_LOG: RefCell<Log> = RefCell::new(
-    Log::create(String::from_str("tp.log").unwrap()).unwrap()
-  );
+/// Configuración del log global, elegida con `Log::init_with_format` y
+/// `Log::init_rotation`
+#[derive(Clone)]
+struct LogConfig {
+  path: String,
+  min_severity: LogSeverity,
+  format: LogFormat,
+  rotation: LogRotation
+}
+
+/// Configuración de `Log::init`
+static LOG_CONFIG: Mutex<Option<LogConfig>> = Mutex::new(None);
+/// Tipo de entidad del proceso, elegido con `Log::set_entity`
+static LOG_ENTITY: Mutex<Option<String>> = Mutex::new(None);
+/// Indica si ya se advirtió sobre las variables de entorno inválidas, para
+/// hacerlo una sola vez por proceso y no en cada hilo
+static ENV_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Instancia única del log accesible en toda la aplicación. Se abre con
+/// la configuración de `Log::init`, o la de por defecto si no se llamó.
+pub static GLOBAL_LOG: GlobalLog = GlobalLog {log: Mutex::new(None)};
+
+/// Log global, compartido por todos los hilos del proceso
+///
+/// Los locks de `fcntl` son por proceso y se liberan al cerrar cualquier
+/// descriptor del archivo, por lo que no excluyen a los hilos entre sí. Por
+/// eso el proceso abre el log una única vez y sus hilos se turnan con un
+/// `Mutex` antes de tomar el lock del archivo.
+pub struct GlobalLog {
+  log: Mutex<Option<Log>>
+}
+
+impl GlobalLog {
+  /// Ejecuta `f` con el log tomado, abriéndolo si todavía no se abrió.
+  /// `f` no debe loguear, ya que el log sigue tomado.
+  pub fn with<F, R>(&self, f: F) -> R where F: FnOnce(&mut Log) -> R {
+    let mut log = self.log.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    f(log.get_or_insert_with(Log::configured))
+  }
+
+  /// Indica si se escriben los mensajes de severidad `severity`
+  pub fn enabled(&self, severity: &LogSeverity) -> bool {
+    self.with(|log| log.enabled(severity))
+  }
+
+  /// Ejecuta `f` con el log tomado, sin abrirlo
+  pub(crate) fn while_locked<F, R>(&self, f: F) -> R where F: FnOnce() -> R {
+    let _log = self.log.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    f()
+  }
+
+  /// Reemplaza el log, cerrando el anterior
+  fn replace(&self, log: Log) {
+    *self.log.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(log);
+  }
 }
 
 /// Recibe un mensaje y severidad del mismo para escribirlos en el log global
 ///
+/// El mensaje sólo se evalúa si la severidad no está filtrada, por lo que
+/// puede armarse dentro del macro con `format!` sin costo para los mensajes
+/// descartados. Los errores de escritura se ignoran.
+///
 /// **Importante**: para usar este macro se debe incluir la variable GLOBAL_LOG
+///
+/// La segunda forma arma un registro estructurado: recibe la severidad, el
+/// nombre del evento, el formato del mensaje con sus argumentos y, luego de
+/// un `;`, los campos del registro como `clave = valor`. Los valores pueden
+/// ser enteros o strings (ver `LogValue`). En formato texto los campos no se
+/// escriben. Esta forma no necesita incluir `GLOBAL_LOG` ni `LogSeverity`.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// #[macro_use(log)]
+/// extern crate concurrentes;
+///
+/// fn main() {
+///   let (passenger, port) = (1234, 2);
+///   log!(INFO, "boarded", "Abordó el pasajero {}", passenger; passenger = passenger, port = port);
+/// }
+/// ```
 #[macro_export]
 macro_rules! log {
   ($msg: expr, $severity: expr) => {{
-    GLOBAL_LOG.with(|log_cell| {
-      let mut log = log_cell.borrow_mut();
-      log.log($msg, $severity);
-    })
+    let severity = $severity;
+    // El mensaje se arma sin tomar el log, por si al armarlo se loguea
+    if GLOBAL_LOG.enabled(severity) {
+      // El match mantiene vivos los temporales del mensaje
+      match $msg {
+        msg => { let _ = GLOBAL_LOG.with(|log| log.log(msg, severity)); }
+      }
+    }
+  }};
+  ($severity: ident, $event: expr, $fmt: expr $(, $arg: expr)* $(; $($key: ident = $value: expr),*)?) => {{
+    let severity = &$crate::log::LogSeverity::$severity;
+    if $crate::log::GLOBAL_LOG.enabled(severity) {
+      let message = format!($fmt $(, $arg)*);
+      let fields: Vec<(&str, $crate::log::LogValue)> = vec![
+        $($((stringify!($key), $crate::log::LogValue::from($value))),*)?
+      ];
+      let _ = $crate::log::GLOBAL_LOG.with(|log| log.log_event(severity, $event, &message, &fields));
+    }
   }}
 }
 
@@ -55,10 +340,166 @@ impl Log {
   ///
   /// * `path`: ruta al archivo sobre el que se escribirá el log
   pub fn create(path: String) -> io::Result<Log> {
+    Log::create_with_severity(path, LogSeverity::DEBUG)
+  }
+
+  /// Igual que `create`, descartando los mensajes menos graves que
+  /// `min_severity`
+  pub fn create_with_severity(path: String, min_severity: LogSeverity) -> io::Result<Log> {
+    let file_lock = Log::open_file(path)?;
+    Ok(Log{file_lock, min_severity, format: LogFormat::Text, rotation: LogRotation::new()})
+  }
+
+  /// Formato con el que se escriben las líneas de aquí en más
+  pub fn set_format(&mut self, format: LogFormat) {
+    self.format = format;
+  }
+
+  /// Política de rotación de aquí en más
+  pub fn set_rotation(&mut self, rotation: LogRotation) {
+    self.rotation = rotation;
+  }
+
+  /// Abre (o crea) el archivo de log en modo append
+  fn open_file(path: String) -> io::Result<FileLock> {
     let mut options = OpenOptions::new();
     options.append(true).create(true);
-    let file_lock = FileLock::new_with_options(path, &options)?;
-    Ok(Log{file_lock})
+    FileLock::new_with_options(path, &options)
+  }
+
+  /// Configura el log global: lo escribe en `path` y descarta los mensajes
+  /// menos graves que `min_severity`. Las variables de entorno
+  /// `CONCU_LOG_PATH` y `CONCU_LOG_LEVEL`, si están definidas, tienen
+  /// prioridad sobre estos valores.
+  ///
+  /// Debe llamarse al comenzar el programa. Reabre el log global, que
+  /// comparten todos los hilos.
+  /// Los procesos creados con `process::fork` la heredan, y los lanzados con
+  /// `exec` sólo heredan las variables de entorno.
+  ///
+  /// # Example
+  ///
+  /// ```rust, no_run
+  /// use concurrentes::log::{Log, LogSeverity};
+  ///
+  /// Log::init("lago.log", LogSeverity::INFO).unwrap();
+  /// ```
+  pub fn init(path: &str, min_severity: LogSeverity) -> io::Result<()> {
+    Log::init_with_format(path, min_severity, LogFormat::Text)
+  }
+
+  /// Igual que `init`, eligiendo además el formato de las líneas. La
+  /// variable de entorno `CONCU_LOG_FORMAT` tiene prioridad sobre `format`.
+  pub fn init_with_format(path: &str, min_severity: LogSeverity, format: LogFormat)
+      -> io::Result<()> {
+    let rotation = Log::current_config().rotation;
+    Log::apply_config(LogConfig {path: path.to_string(), min_severity, format, rotation})
+  }
+
+  /// Configura la rotación del log global, manteniendo el resto de su
+  /// configuración. Las variables de entorno `CONCU_LOG_MAX_BYTES`,
+  /// `CONCU_LOG_ROTATE_SECS` y `CONCU_LOG_KEEP` tienen prioridad sobre
+  /// `rotation`.
+  ///
+  /// Todos los procesos que escriben el log deberían usar la misma política.
+  /// Los que no la tengan no rotan, pero igual reabren el log rotado por otro.
+  pub fn init_rotation(rotation: &LogRotation) -> io::Result<()> {
+    let mut config = Log::current_config();
+    config.rotation = *rotation;
+    Log::apply_config(config)
+  }
+
+  /// Aplica las variables de entorno a `config`, la guarda como configuración
+  /// global y reabre el log global
+  fn apply_config(config: LogConfig) -> io::Result<()> {
+    let (config, warnings) = Log::apply_env(config);
+    let mut log = Log::open(&config)?;
+    log.warn_env(&warnings);
+    *LOG_CONFIG.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(config);
+    GLOBAL_LOG.replace(log);
+    Ok(())
+  }
+
+  /// Tipo de entidad (por ejemplo `ship`) que se registra en cada línea en
+  /// formato JSON. Vale para todo el proceso.
+  pub fn set_entity(entity: &str) {
+    *LOG_ENTITY.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(entity.to_string());
+  }
+
+  /// Abre el log con la configuración de `init`, o con la de por defecto
+  /// (`tp.log`, todas las severidades, formato texto) y las variables de entorno.
+  ///
+  /// Si el log configurado no se puede abrir, advierte y usa el de por
+  /// defecto. Sólo falla si tampoco puede abrir `tp.log`.
+  fn configured() -> Log {
+    let (config, mut warnings) = Log::apply_env(Log::current_config());
+    let mut log = match Log::open(&config) {
+      Ok(log) => log,
+      Err(e) => {
+        warnings.push(format!("No se pudo abrir el log {}: {}, se usa {}", config.path, e,
+          DEFAULT_LOG_PATH));
+        Log::open(&Log::default_config()).expect("No se pudo abrir el log por defecto")
+      }
+    };
+    log.warn_env(&warnings);
+    log
+  }
+
+  /// Configuración de `init`, o la de por defecto
+  fn current_config() -> LogConfig {
+    let config = LOG_CONFIG.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+    config.unwrap_or_else(Log::default_config)
+  }
+
+  /// `tp.log`, todas las severidades, formato texto y sin rotación
+  fn default_config() -> LogConfig {
+    LogConfig {path: DEFAULT_LOG_PATH.to_string(), min_severity: LogSeverity::DEBUG,
+      format: LogFormat::Text, rotation: LogRotation::new()}
+  }
+
+  /// Escribe las advertencias sobre las variables de entorno, una sola vez
+  /// por proceso
+  fn warn_env(&mut self, warnings: &[String]) {
+    if warnings.is_empty() || ENV_WARNED.swap(true, Ordering::SeqCst) {
+      return;
+    }
+    for warning in warnings {
+      let _ = self.log(warning, &LogSeverity::WARN);
+    }
+  }
+
+  /// Abre el log descripto por `config`
+  fn open(config: &LogConfig) -> io::Result<Log> {
+    let mut log = Log::create_with_severity(config.path.clone(), config.min_severity)?;
+    log.set_format(config.format);
+    log.set_rotation(config.rotation);
+    Ok(log)
+  }
+
+  /// Reemplaza la configuración por la de las variables de entorno. Las
+  /// variables con valores inválidos se ignoran, y se devuelve una
+  /// advertencia por cada una.
+  fn apply_env(config: LogConfig) -> (LogConfig, Vec<String>) {
+    let mut warnings = Vec::new();
+    let path = env::var(LOG_PATH_VAR).unwrap_or(config.path);
+    let min_severity = env_value(LOG_LEVEL_VAR, &mut warnings).unwrap_or(config.min_severity);
+    let format = env_value(LOG_FORMAT_VAR, &mut warnings).unwrap_or(config.format);
+    let mut rotation = config.rotation;
+    if let Some(bytes) = env_value(LOG_MAX_BYTES_VAR, &mut warnings) {
+      rotation.max_bytes(bytes);
+    }
+    if let Some(secs) = env_value(LOG_ROTATE_SECS_VAR, &mut warnings) {
+      rotation.interval(Duration::from_secs(secs));
+    }
+    if let Some(files) = env_value(LOG_KEEP_VAR, &mut warnings) {
+      rotation.keep(files);
+    }
+    (LogConfig {path, min_severity, format, rotation}, warnings)
+  }
+
+  /// Indica si se escriben los mensajes de severidad `severity`
+  pub fn enabled(&self, severity: &LogSeverity) -> bool {
+    *severity <= self.min_severity
   }
 
   /// Recibe un mensaje y un tipo (severidad) de mensaje, y lo escribe en el log compartido
@@ -66,14 +507,127 @@ impl Log {
   ///
   /// `<fecha> <process-id> <severidad> - mensaje`
   /// 
-  /// Se utiliza un lock exclusivo sincronizar la escritura
+  /// Se utiliza un lock exclusivo sincronizar la escritura. Los mensajes
+  /// filtrados por la severidad mínima se descartan sin tomar el lock.
   pub fn log(&mut self, message: &str, severity: &LogSeverity) -> io::Result<()>{
-    self.file_lock.lock_exclusive()?;
+    self.log_event(severity, DEFAULT_EVENT, message, &[])
+  }
+
+  /// Escribe un registro estructurado: el evento `event`, con su mensaje y
+  /// sus campos. En formato JSON la línea queda
+  ///
+  /// `{"timestamp": ..., "pid": ..., "severity": ..., "entity": ..., "event": ..., "message": ..., <campos>}`
+  ///
+  /// y en formato texto se escribe igual que `log`, sin los campos.
+  pub fn log_event(&mut self, severity: &LogSeverity, event: &str, message: &str,
+      fields: &[(&str, LogValue)]) -> io::Result<()> {
+    if !self.enabled(severity) {
+      return Ok(());
+    }
     let date = Local::now();
-    let date_str = date.format("%Y-%m-%d %H:%M:%S");
     let id = process::id();
-    let fmt_msg = format!("{} [{}] [{:?}] - {}\n", date_str, id, severity, message);
+    let fmt_msg = match self.format {
+      LogFormat::Text => {
+        let date_str = date.format("%Y-%m-%d %H:%M:%S");
+        format!("{} [{}] [{:?}] - {}\n", date_str, id, severity, message)
+      },
+      LogFormat::Json => {
+        let entity = LOG_ENTITY.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+        let entity = entity.map_or("null".to_string(), |entity| json_string(&entity));
+        let mut line = format!("{{\"timestamp\":{},\"pid\":{},\"severity\":\"{:?}\",\
+          \"entity\":{},\"event\":{},\"message\":{}",
+          json_string(&date.format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string()), id, severity,
+          entity, json_string(event), json_string(message));
+        for (key, value) in fields {
+          line.push_str(&format!(",{}:{}", json_string(key), value.to_json()));
+        }
+        line.push_str("}\n");
+        line
+      }
+    };
+    self.lock_current()?;
+    let metadata = self.file_lock.file.metadata()?;
+    if self.rotation.should_rotate(&metadata) {
+      self.rotate()?;
+    }
     self.file_lock.file.write_all(fmt_msg.as_bytes())?;
     self.file_lock.unlock()
   }
+
+  /// Toma el lock exclusivo del log actual. Si el archivo abierto ya no es
+  /// el de la ruta del log (otro proceso lo rotó), reabre la ruta y vuelve
+  /// a intentar.
+  fn lock_current(&mut self) -> io::Result<()> {
+    loop {
+      self.file_lock.lock_exclusive()?;
+      let opened = self.file_lock.file.metadata()?;
+      match fs::metadata(&self.file_lock.path) {
+        Ok(ref current) if current.dev() == opened.dev() && current.ino() == opened.ino() =>
+          return Ok(()),
+        Ok(_) => {},
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {},
+        Err(e) => {
+          self.file_lock.unlock()?;
+          return Err(e);
+        }
+      }
+      // Al cerrar el archivo anterior se libera su lock
+      self.file_lock = Log::open_file(self.file_lock.path.clone())?;
+    }
+  }
+
+  /// Rota el log teniendo tomado su lock: desplaza los archivos rotados,
+  /// renombra el actual y pasa a escribir uno nuevo. El lock del archivo
+  /// rotado se suelta recién con el del nuevo tomado, para que ningún
+  /// proceso escriba entre medio.
+  fn rotate(&mut self) -> io::Result<()> {
+    let path = self.file_lock.path.clone();
+    let keep = self.rotation.keep;
+    let _ = fs::remove_file(format!("{}.{}", path, keep));
+    for n in (1..keep).rev() {
+      // Los archivos que todavía no existen se saltean
+      if let Err(e) = fs::rename(format!("{}.{}", path, n), format!("{}.{}", path, n + 1)) {
+        if e.kind() != io::ErrorKind::NotFound {
+          return Err(e);
+        }
+      }
+    }
+    fs::rename(&path, format!("{}.1", path))?;
+    let mut file_lock = Log::open_file(path)?;
+    file_lock.lock_exclusive()?;
+    self.file_lock = file_lock;
+    Ok(())
+  }
+}
+
+/// Valor de la variable de entorno `name`, si está definida y es válido. Si
+/// no se puede interpretar, agrega una advertencia a `warnings`.
+fn env_value<T: FromStr>(name: &str, warnings: &mut Vec<String>) -> Option<T> {
+  let value = env::var(name).ok()?;
+  match value.trim().parse() {
+    Ok(parsed) => Some(parsed),
+    Err(_) => {
+      warnings.push(format!("Valor inválido en {}: {:?}, se ignora", name, value));
+      None
+    }
+  }
+}
+
+/// Representación JSON de un string, con las comillas y los escapes
+fn json_string(value: &str) -> String {
+  let mut json = String::with_capacity(value.len() + 2);
+  json.push('"');
+  for c in value.chars() {
+    match c {
+      '"' => json.push_str("\\\""),
+      '\\' => json.push_str("\\\\"),
+      '\n' => json.push_str("\\n"),
+      '\r' => json.push_str("\\r"),
+      '\t' => json.push_str("\\t"),
+      c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+      c => json.push(c)
+    }
+  }
+  json.push('"');
+  json
 }
@@ -0,0 +1,380 @@
+use ipc::{IpcOptions, Key};
+use ipc::semaphore::Semaphore;
+use std::io;
+
+/// Obtiene el semáforo número `proj_id` asociado al archivo `path`.
+///
+/// Los patrones que necesitan más de un semáforo usan el mismo archivo de
+/// clave, variando el `proj_id` de `ftok`. Sus semáforos no usan
+/// `SEM_UNDO`, porque en general los incrementa un proceso y los decrementa
+/// otro.
+fn get_semaphore(path: &str, proj_id: u8, options: &IpcOptions) -> io::Result<Semaphore> {
+  let key = Key::ftok(path, proj_id)?;
+  let mut sem = Semaphore::get(&key, options)?;
+  sem.set_undo(false);
+  Ok(sem)
+}
+
+/// Molinete: permite pasar a los procesos de a uno por vez.
+///
+/// Un proceso puede "trabar" el molinete con `lock` para impedir que pasen
+/// los demás mientras realiza una operación, y luego liberarlo con `unlock`.
+///
+/// Utiliza un único semáforo (`proj_id` 0) inicializado en 1.
+pub struct Turnstile {
+  sem: Semaphore
+}
+
+impl Turnstile {
+  /// Obtiene el molinete asociado al archivo `path`
+  pub fn get(path: &str, options: &IpcOptions) -> io::Result<Turnstile> {
+    let sem = get_semaphore(path, 0, options)?;
+    Ok(Turnstile {sem})
+  }
+
+  /// Inicializa el molinete abierto. Sólo debe llamarlo el creador.
+  pub fn init(&self) -> io::Result<()> {
+    self.sem.init(1)
+  }
+
+  /// Pasa por el molinete, bloqueándose si está trabado
+  pub fn pass(&self) -> io::Result<()> {
+    self.sem.wait()?;
+    self.sem.signal()
+  }
+
+  /// Traba el molinete, bloqueándose si otro proceso ya lo hizo
+  pub fn lock(&self) -> io::Result<()> {
+    self.sem.wait()
+  }
+
+  /// Libera el molinete trabado con `lock`
+  pub fn unlock(&self) -> io::Result<()> {
+    self.sem.signal()
+  }
+
+  /// Elimina el IPC del sistema
+  pub fn remove(&mut self) {
+    self.sem.remove();
+  }
+}
+
+/// Interruptor de luz: el primer proceso en entrar a una "habitación" toma
+/// el semáforo `room` y el último en salir lo libera.
+///
+/// Permite que varios procesos de una misma categoría compartan un recurso
+/// excluyendo a los de otra categoría (por ejemplo, lectores y escritores).
+///
+/// Utiliza un mutex (`proj_id` 0) y un contador (`proj_id` 1).
+pub struct Lightswitch {
+  mutex: Semaphore,
+  counter: Semaphore
+}
+
+impl Lightswitch {
+  /// Obtiene el interruptor asociado al archivo `path`
+  pub fn get(path: &str, options: &IpcOptions) -> io::Result<Lightswitch> {
+    let mutex = get_semaphore(path, 0, options)?;
+    let counter = get_semaphore(path, 1, options)?;
+    Ok(Lightswitch {mutex, counter})
+  }
+
+  /// Inicializa el interruptor sin procesos dentro. Sólo debe llamarlo el creador.
+  pub fn init(&self) -> io::Result<()> {
+    self.mutex.init(1)?;
+    self.counter.init(0)
+  }
+
+  /// Entra a la habitación. Si es el primero, espera a tomar `room`, que
+  /// no debería usar `SEM_UNDO`: puede liberarlo otro proceso.
+  pub fn lock(&self, room: &Semaphore) -> io::Result<()> {
+    self.mutex.wait()?;
+    self.counter.signal()?;
+    if self.counter.get_value()? == 1 {
+      room.wait()?;
+    }
+    self.mutex.signal()
+  }
+
+  /// Sale de la habitación. Si es el último, libera `room`.
+  pub fn unlock(&self, room: &Semaphore) -> io::Result<()> {
+    self.mutex.wait()?;
+    self.counter.wait()?;
+    if self.counter.get_value()? == 0 {
+      room.signal()?;
+    }
+    self.mutex.signal()
+  }
+
+  /// Elimina los IPCs del sistema
+  pub fn remove(&mut self) {
+    self.mutex.remove();
+    self.counter.remove();
+  }
+}
+
+/// Multiplex: permite que hasta `n` procesos estén dentro de una sección
+/// crítica al mismo tiempo.
+///
+/// Utiliza un único semáforo (`proj_id` 0) inicializado en `n`.
+pub struct Multiplex {
+  sem: Semaphore
+}
+
+impl Multiplex {
+  /// Obtiene el multiplex asociado al archivo `path`
+  pub fn get(path: &str, options: &IpcOptions) -> io::Result<Multiplex> {
+    let sem = get_semaphore(path, 0, options)?;
+    Ok(Multiplex {sem})
+  }
+
+  /// Inicializa el multiplex con `n` lugares. Sólo debe llamarlo el creador.
+  pub fn init(&self, n: i32) -> io::Result<()> {
+    self.sem.init(n)
+  }
+
+  /// Ocupa un lugar, bloqueándose si no queda ninguno libre
+  pub fn enter(&self) -> io::Result<()> {
+    self.sem.wait()
+  }
+
+  /// Libera el lugar ocupado con `enter`
+  pub fn leave(&self) -> io::Result<()> {
+    self.sem.signal()
+  }
+
+  /// Elimina el IPC del sistema
+  pub fn remove(&mut self) {
+    self.sem.remove();
+  }
+}
+
+/// Punto de encuentro entre dos procesos, `A` y `B`. Ninguno de los dos
+/// continúa hasta que el otro haya llegado.
+///
+/// Utiliza un semáforo de llegada por cada lado (`proj_id` 0 y 1).
+pub struct Rendezvous {
+  a_arrived: Semaphore,
+  b_arrived: Semaphore
+}
+
+impl Rendezvous {
+  /// Obtiene el punto de encuentro asociado al archivo `path`
+  pub fn get(path: &str, options: &IpcOptions) -> io::Result<Rendezvous> {
+    let a_arrived = get_semaphore(path, 0, options)?;
+    let b_arrived = get_semaphore(path, 1, options)?;
+    Ok(Rendezvous {a_arrived, b_arrived})
+  }
+
+  /// Inicializa el punto de encuentro vacío. Sólo debe llamarlo el creador.
+  pub fn init(&self) -> io::Result<()> {
+    self.a_arrived.init(0)?;
+    self.b_arrived.init(0)
+  }
+
+  /// Llegada del proceso `A`: avisa y espera a `B`
+  pub fn arrive_a(&self) -> io::Result<()> {
+    self.a_arrived.signal()?;
+    self.b_arrived.wait()
+  }
+
+  /// Llegada del proceso `B`: avisa y espera a `A`
+  pub fn arrive_b(&self) -> io::Result<()> {
+    self.b_arrived.signal()?;
+    self.a_arrived.wait()
+  }
+
+  /// Elimina los IPCs del sistema
+  pub fn remove(&mut self) {
+    self.a_arrived.remove();
+    self.b_arrived.remove();
+  }
+}
+
+/// Barrera reutilizable para `n` procesos. Ninguno continúa hasta que
+/// lleguen todos, y la barrera puede volver a usarse inmediatamente.
+///
+/// Implementada con dos molinetes, como en "The Little Book of Semaphores".
+/// Utiliza un mutex (`proj_id` 0), un contador (`proj_id` 1) y dos
+/// molinetes (`proj_id` 2 y 3).
+pub struct Barrier {
+  n: i32,
+  mutex: Semaphore,
+  counter: Semaphore,
+  turnstile: Semaphore,
+  turnstile2: Semaphore
+}
+
+impl Barrier {
+  /// Obtiene la barrera de `n` procesos asociada al archivo `path`
+  pub fn get(path: &str, n: i32, options: &IpcOptions) -> io::Result<Barrier> {
+    let mutex = get_semaphore(path, 0, options)?;
+    let counter = get_semaphore(path, 1, options)?;
+    let turnstile = get_semaphore(path, 2, options)?;
+    let turnstile2 = get_semaphore(path, 3, options)?;
+    Ok(Barrier {n, mutex, counter, turnstile, turnstile2})
+  }
+
+  /// Inicializa la barrera vacía. Sólo debe llamarlo el creador.
+  pub fn init(&self) -> io::Result<()> {
+    self.mutex.init(1)?;
+    self.counter.init(0)?;
+    self.turnstile.init(0)?;
+    self.turnstile2.init(1)
+  }
+
+  /// Espera a que lleguen los `n` procesos
+  pub fn wait(&self) -> io::Result<()> {
+    // Primera fase: el último en llegar abre el primer molinete
+    self.mutex.wait()?;
+    self.counter.signal()?;
+    if self.counter.get_value()? == self.n {
+      self.turnstile2.wait()?;
+      self.turnstile.signal()?;
+    }
+    self.mutex.signal()?;
+    self.turnstile.wait()?;
+    self.turnstile.signal()?;
+    // Segunda fase: el último en salir cierra el primero y abre el segundo
+    self.mutex.wait()?;
+    self.counter.wait()?;
+    if self.counter.get_value()? == 0 {
+      self.turnstile.wait()?;
+      self.turnstile2.signal()?;
+    }
+    self.mutex.signal()?;
+    self.turnstile2.wait()?;
+    self.turnstile2.signal()
+  }
+
+  /// Elimina los IPCs del sistema
+  pub fn remove(&mut self) {
+    self.mutex.remove();
+    self.counter.remove();
+    self.turnstile.remove();
+    self.turnstile2.remove();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use libc;
+  use process::{self, ExitStatus, ForkResult};
+  use std::env;
+  use std::fs::{File, remove_file};
+  use std::process::id as pid;
+
+  /// Archivo de clave propio de cada test, para que no compartan semáforos
+  fn key_file(name: &str) -> String {
+    let path = env::temp_dir().join(format!("concu-patterns-{}-{}", name, pid()));
+    File::create(&path).unwrap();
+    path.to_string_lossy().into_owned()
+  }
+
+  fn options() -> IpcOptions {
+    IpcOptions::new().create(true).mode(0o600).clone()
+  }
+
+  /// Ejecuta `f` en un proceso hijo, que termina con código 0 si no falló
+  fn spawn<F: FnOnce() -> io::Result<()>>(f: F) -> libc::pid_t {
+    match process::fork().unwrap() {
+      ForkResult::Parent {child} => child,
+      ForkResult::Child => {
+        let code = if f().is_ok() { 0 } else { 1 };
+        unsafe { libc::_exit(code) }
+      }
+    }
+  }
+
+  fn join(child: libc::pid_t) {
+    assert_eq!(process::waitpid(child).unwrap().1, ExitStatus::Exited(0));
+  }
+
+  #[test]
+  fn turnstile_can_be_unlocked_by_another_process() {
+    let path = key_file("turnstile");
+    let mut turnstile = Turnstile::get(&path, &options()).unwrap();
+    turnstile.init().unwrap();
+    turnstile.lock().unwrap();
+    join(spawn(|| turnstile.unlock()));
+    // Sin SEM_UNDO el desbloqueo sobrevive al hijo
+    assert_eq!(turnstile.sem.get_value().unwrap(), 1);
+    join(spawn(|| turnstile.pass()));
+    assert_eq!(turnstile.sem.get_value().unwrap(), 1);
+    turnstile.remove();
+    remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn lightswitch_keeps_the_room_while_someone_is_inside() {
+    let path = key_file("lightswitch");
+    let mut lightswitch = Lightswitch::get(&path, &options()).unwrap();
+    lightswitch.init().unwrap();
+    let mut room = get_semaphore(&path, 2, &options()).unwrap();
+    room.init(1).unwrap();
+    // Entran dos procesos y salen otros dos
+    join(spawn(|| lightswitch.lock(&room)));
+    assert_eq!(room.get_value().unwrap(), 0);
+    join(spawn(|| lightswitch.lock(&room)));
+    assert_eq!(lightswitch.counter.get_value().unwrap(), 2);
+    join(spawn(|| lightswitch.unlock(&room)));
+    assert_eq!(room.get_value().unwrap(), 0);
+    join(spawn(|| lightswitch.unlock(&room)));
+    assert_eq!(lightswitch.counter.get_value().unwrap(), 0);
+    assert_eq!(room.get_value().unwrap(), 1);
+    lightswitch.remove();
+    room.remove();
+    remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn multiplex_places_are_kept_after_the_process_ends() {
+    let path = key_file("multiplex");
+    let mut multiplex = Multiplex::get(&path, &options()).unwrap();
+    multiplex.init(2).unwrap();
+    join(spawn(|| multiplex.enter()));
+    join(spawn(|| multiplex.enter()));
+    assert_eq!(multiplex.sem.get_value().unwrap(), 0);
+    join(spawn(|| multiplex.leave()));
+    assert_eq!(multiplex.sem.get_value().unwrap(), 1);
+    multiplex.remove();
+    remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn rendezvous_releases_both_processes() {
+    let path = key_file("rendezvous");
+    let mut rendezvous = Rendezvous::get(&path, &options()).unwrap();
+    rendezvous.init().unwrap();
+    let child = spawn(|| rendezvous.arrive_b());
+    rendezvous.arrive_a().unwrap();
+    join(child);
+    assert_eq!(rendezvous.a_arrived.get_value().unwrap(), 0);
+    assert_eq!(rendezvous.b_arrived.get_value().unwrap(), 0);
+    rendezvous.remove();
+    remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn barrier_can_be_reused() {
+    let path = key_file("barrier");
+    let mut barrier = Barrier::get(&path, 3, &options()).unwrap();
+    barrier.init().unwrap();
+    let children: Vec<_> = (0..2).map(|_| spawn(|| {
+      barrier.wait()?;
+      barrier.wait()
+    })).collect();
+    barrier.wait().unwrap();
+    barrier.wait().unwrap();
+    for child in children {
+      join(child);
+    }
+    // Vuelve al estado inicial una vez que terminaron los hijos
+    assert_eq!(barrier.counter.get_value().unwrap(), 0);
+    assert_eq!(barrier.turnstile.get_value().unwrap(), 0);
+    assert_eq!(barrier.turnstile2.get_value().unwrap(), 1);
+    barrier.remove();
+    remove_file(path).unwrap();
+  }
+}
@@ -1,11 +1,35 @@
-use libc::pid_t;
+use libc;
+use libc::{c_int, pid_t};
 use libc::fork as c_fork;
 use libc::waitpid as c_waitpid;
-use std::io::Error;
+use log::GLOBAL_LOG;
+use signal;
+use signal::{SignalHandler, SignalHandlerDispatcher, SignalOptions};
+use std::env;
+use std::ffi::CString;
+use std::fmt;
+use std::mem;
+use std::io::{Error, ErrorKind};
+use std::os::unix::io::RawFd;
 use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 pub const ANY_CHILD: pid_t = -1;
 
+/// Opción de `waitpid_with_options`: informa también los hijos detenidos
+pub const WUNTRACED: c_int = libc::WUNTRACED;
+/// Opción de `waitpid_with_options`: informa también los hijos reanudados
+pub const WCONTINUED: c_int = libc::WCONTINUED;
+/// Opción de `waitpid_with_options`: no se bloquea si ningún hijo cambió de estado
+pub const WNOHANG: c_int = libc::WNOHANG;
+
+/// Argumento de `getrusage`: recursos consumidos por el proceso actual
+pub const RUSAGE_SELF: c_int = libc::RUSAGE_SELF;
+/// Argumento de `getrusage`: recursos consumidos por los hijos ya unidos
+pub const RUSAGE_CHILDREN: c_int = libc::RUSAGE_CHILDREN;
+
 /// libc `fork()` result wrapper
 
 pub enum ForkResult {
@@ -13,17 +37,79 @@ pub enum ForkResult {
   Child
 }
 
+/// Estado de un proceso hijo, decodificado del `status` de `waitpid`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ExitStatus {
+  /// Terminó normalmente con el código de salida indicado
+  Exited(i32),
+  /// Fue terminado por la señal indicada
+  Signaled(i32),
+  /// Fue terminado por la señal indicada y generó un core dump
+  CoreDumped(i32),
+  /// Fue detenido por la señal indicada (sólo con `WUNTRACED`)
+  Stopped(i32),
+  /// Fue reanudado con `SIGCONT` (sólo con `WCONTINUED`)
+  Continued
+}
+
+impl ExitStatus {
+  /// Decodifica el `status` devuelto por `waitpid`
+  pub fn from_raw(status: c_int) -> ExitStatus {
+    if libc::WIFEXITED(status) {
+      ExitStatus::Exited(libc::WEXITSTATUS(status))
+    } else if libc::WIFSIGNALED(status) {
+      if libc::WCOREDUMP(status) {
+        ExitStatus::CoreDumped(libc::WTERMSIG(status))
+      } else {
+        ExitStatus::Signaled(libc::WTERMSIG(status))
+      }
+    } else if libc::WIFSTOPPED(status) {
+      ExitStatus::Stopped(libc::WSTOPSIG(status))
+    } else {
+      ExitStatus::Continued
+    }
+  }
+
+  /// Devuelve `true` si el proceso terminó normalmente con código 0
+  pub fn success(&self) -> bool {
+    *self == ExitStatus::Exited(0)
+  }
+
+  /// Devuelve `true` si el proceso terminó (y no sólo cambió de estado)
+  pub fn is_terminated(&self) -> bool {
+    match *self {
+      ExitStatus::Exited(_) | ExitStatus::Signaled(_) | ExitStatus::CoreDumped(_) => true,
+      ExitStatus::Stopped(_) | ExitStatus::Continued => false
+    }
+  }
+}
+
+impl fmt::Display for ExitStatus {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      ExitStatus::Exited(code) => write!(f, "terminó con código {}", code),
+      ExitStatus::Signaled(signal) => write!(f, "fue terminado por la señal {}", signal),
+      ExitStatus::CoreDumped(signal) =>
+        write!(f, "fue terminado por la señal {} (core dump)", signal),
+      ExitStatus::Stopped(signal) => write!(f, "fue detenido por la señal {}", signal),
+      ExitStatus::Continued => write!(f, "fue reanudado")
+    }
+  }
+}
+
 /// libc `fork()`
 /// * On parent process returns ForkResult::Parent with the new child's pid_t.
 /// * On child process returns ForkResult::Child.
 /// * On failure returns associated error.
-
+///
+/// El hijo crea su propio self-pipe de señales (ver
+/// `signal::SignalHandlerDispatcher`), para no compartirlo con el padre.
+/// El log global se toma durante el `fork`, para que el hijo no lo herede
+/// tomado por otro hilo.
 pub fn fork() -> Result<ForkResult, Error> {
-  let pid;
-  unsafe {
-    pid = c_fork();
-  }
+  let pid = GLOBAL_LOG.while_locked(|| unsafe { c_fork() });
   if pid == 0 {
+    signal::reopen_after_fork();
     Ok(ForkResult::Child)
   } else if pid > 0 {
     Ok(ForkResult::Parent{child: pid})
@@ -32,16 +118,651 @@ pub fn fork() -> Result<ForkResult, Error> {
   }
 }
 
+/// Ejecuta `program` en un nuevo proceso hijo (`fork` + `execvpe`).
+///
+/// * `program` se busca en el `PATH` si no contiene `/`, y es también el
+///   `argv[0]` del nuevo proceso.
+/// * `args` son los argumentos que siguen a `argv[0]`.
+/// * `env` son variables que se agregan (o reemplazan) al entorno heredado.
+///
+/// Sólo los file descriptors sin close-on-exec (ver `set_cloexec`) quedan
+/// abiertos en el nuevo programa. Si el `exec` falla, el error se informa al
+/// padre a través de un pipe y el hijo termina con código 127.
+///
+/// Devuelve el pid del hijo, que debe unirse con `waitpid`.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use concurrentes::process;
+///
+/// let child = process::spawn("ls", &["-l"], &[("LC_ALL", "C")]).unwrap();
+/// process::waitpid(child).unwrap();
+/// ```
+pub fn spawn(program: &str, args: &[&str], env: &[(&str, &str)]) -> Result<pid_t, Error> {
+  spawn_with_options(program, args, env, &SpawnOptions::new())
+}
+
+/// Opciones que `spawn_with_options` aplica en el hijo antes del `exec`
+///
+/// Permiten mover al hijo a un grupo de procesos y elegir la señal que
+/// recibe cuando muere el padre.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SpawnOptions {
+  process_group: Option<pid_t>,
+  death_signal: Option<c_int>
+}
+
+impl SpawnOptions {
+  /// Opciones por defecto: el hijo hereda el grupo del padre y no es
+  /// notificado si el padre muere
+  pub fn new() -> SpawnOptions {
+    SpawnOptions {process_group: None, death_signal: None}
+  }
+
+  /// Mueve al hijo al grupo de procesos `pgid` (0 crea un grupo nuevo, con
+  /// el hijo como líder)
+  pub fn process_group(&mut self, pgid: pid_t) -> &mut SpawnOptions {
+    self.process_group = Some(pgid);
+    self
+  }
+
+  /// Señal que recibe el hijo cuando muere el padre (ver `set_parent_death_signal`)
+  pub fn parent_death_signal(&mut self, signal: c_int) -> &mut SpawnOptions {
+    self.death_signal = Some(signal);
+    self
+  }
+
+  /// Aplica las opciones en el proceso hijo. Sólo realiza llamadas seguras
+  /// entre `fork` y `exec`.
+  fn apply(&self, parent: pid_t) -> Result<(), Error> {
+    if let Some(pgid) = self.process_group {
+      join_process_group(pgid)?;
+    }
+    if let Some(signal) = self.death_signal {
+      set_parent_death_signal(signal, parent)?;
+    }
+    Ok(())
+  }
+}
+
+/// Igual que `spawn`, pero aplica `options` en el hijo antes del `exec`
+pub fn spawn_with_options(program: &str, args: &[&str], env: &[(&str, &str)],
+    options: &SpawnOptions) -> Result<pid_t, Error> {
+  // Todas las reservas de memoria se hacen antes del fork
+  let program = CString::new(program)?;
+  let mut argv = vec![program.clone()];
+  for arg in args {
+    argv.push(CString::new(*arg)?);
+  }
+  let mut envp = Vec::new();
+  for (key, value) in env::vars() {
+    if !env.iter().any(|&(new_key, _)| new_key == key) {
+      envp.push(CString::new(format!("{}={}", key, value))?);
+    }
+  }
+  for &(key, value) in env {
+    envp.push(CString::new(format!("{}={}", key, value))?);
+  }
+  let mut argv_ptrs: Vec<_> = argv.iter().map(|arg| arg.as_ptr()).collect();
+  argv_ptrs.push(ptr::null());
+  let mut envp_ptrs: Vec<_> = envp.iter().map(|var| var.as_ptr()).collect();
+  envp_ptrs.push(ptr::null());
+  // Pipe para informar la falla del exec. Se cierra solo si el exec funciona.
+  let mut fds: [c_int; 2] = [0; 2];
+  if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } == -1 {
+    return Err(Error::last_os_error());
+  }
+  let (read_fd, write_fd) = (fds[0], fds[1]);
+  let parent = unsafe { libc::getpid() };
+  match fork() {
+    Ok(ForkResult::Child) => unsafe {
+      libc::close(read_fd);
+      let error = match options.apply(parent) {
+        Ok(()) => {
+          libc::execvpe(program.as_ptr(), argv_ptrs.as_ptr(), envp_ptrs.as_ptr());
+          Error::last_os_error()
+        },
+        Err(e) => e
+      };
+      let errno = error.raw_os_error().unwrap_or(0);
+      let bytes = errno.to_ne_bytes();
+      libc::write(write_fd, bytes.as_ptr() as *const _, bytes.len());
+      libc::_exit(127)
+    },
+    Ok(ForkResult::Parent{child}) => {
+      unsafe { libc::close(write_fd) };
+      let mut bytes = [0u8; 4];
+      let read = loop {
+        let read = unsafe { libc::read(read_fd, bytes.as_mut_ptr() as *mut _, bytes.len()) };
+        if read != -1 || Error::last_os_error().kind() != ErrorKind::Interrupted {
+          break read;
+        }
+      };
+      unsafe { libc::close(read_fd) };
+      if read == bytes.len() as isize {
+        // El exec falló: se une al hijo y se devuelve el error
+        waitpid(child)?;
+        Err(Error::from_raw_os_error(c_int::from_ne_bytes(bytes)))
+      } else {
+        Ok(child)
+      }
+    },
+    Err(e) => {
+      unsafe {
+        libc::close(read_fd);
+        libc::close(write_fd);
+      }
+      Err(e)
+    }
+  }
+}
+
+/// Activa o desactiva el flag close-on-exec de `fd`.
+///
+/// Los file descriptors con el flag activo se cierran al ejecutar otro
+/// programa (ver `spawn`); el resto es heredado por el nuevo programa.
+pub fn set_cloexec(fd: RawFd, cloexec: bool) -> Result<(), Error> {
+  unsafe {
+    let flags = libc::fcntl(fd, libc::F_GETFD);
+    if flags == -1 {
+      return Err(Error::last_os_error());
+    }
+    let flags = if cloexec { flags | libc::FD_CLOEXEC } else { flags & !libc::FD_CLOEXEC };
+    if libc::fcntl(fd, libc::F_SETFD, flags) == -1 {
+      return Err(Error::last_os_error());
+    }
+  }
+  Ok(())
+}
+
+/// Qué hace un `ChildHandle` con su proceso si es destruido sin haberlo unido
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DropPolicy {
+  /// No hace nada: el hijo sigue corriendo y debe unirse por otro medio
+  /// (por ejemplo, con `ChildReaper`)
+  Detach,
+  /// Espera a que el hijo termine
+  Join,
+  /// Mata al hijo con `SIGKILL` y lo une
+  Kill
+}
+
+/// Proceso hijo lanzado con `spawn_fn`
+///
+/// Por defecto, al destruirse sin haber sido unido no hace nada con el hijo
+/// (`DropPolicy::Detach`), igual que `std::thread::JoinHandle`.
+#[derive(Debug)]
+pub struct ChildHandle {
+  pid: pid_t,
+  status: Option<ExitStatus>,
+  drop_policy: DropPolicy
+}
+
+impl ChildHandle {
+  /// Pid del proceso hijo
+  pub fn pid(&self) -> pid_t {
+    self.pid
+  }
+
+  /// Cambia lo que se hace con el hijo al destruir el handle
+  pub fn set_drop_policy(&mut self, policy: DropPolicy) {
+    self.drop_policy = policy;
+  }
+
+  /// Espera a que el hijo termine y devuelve su estado de salida. Si ya fue
+  /// unido, devuelve el estado obtenido la primera vez.
+  pub fn join(&mut self) -> Result<ExitStatus, Error> {
+    if let Some(status) = self.status {
+      return Ok(status);
+    }
+    loop {
+      match waitpid(self.pid) {
+        Ok((_, status)) => {
+          self.status = Some(status);
+          return Ok(status);
+        },
+        Err(ref e) if e.kind() == ErrorKind::Interrupted => {},
+        Err(e) => return Err(e)
+      }
+    }
+  }
+
+  /// Envía la señal `signal` al hijo
+  pub fn kill(&self, signal: c_int) -> Result<(), Error> {
+    if self.status.is_some() {
+      return Err(Error::new(ErrorKind::NotFound, "El proceso ya fue unido"));
+    }
+    if unsafe { libc::kill(self.pid, signal) } == -1 {
+      Err(Error::last_os_error())
+    } else {
+      Ok(())
+    }
+  }
+}
+
+impl Drop for ChildHandle {
+  /// Aplica la política de destrucción si el hijo no fue unido
+  fn drop(&mut self) {
+    if self.status.is_some() {
+      return;
+    }
+    match self.drop_policy {
+      DropPolicy::Detach => {},
+      DropPolicy::Join => {
+        let _ = self.join();
+      },
+      DropPolicy::Kill => {
+        let _ = self.kill(libc::SIGKILL);
+        let _ = self.join();
+      }
+    }
+  }
+}
+
+/// Ejecuta `f` en un nuevo proceso hijo (`fork`) y devuelve un handle para
+/// unirlo.
+///
+/// El hijo termina al finalizar `f`: con código 0 si devuelve `Ok`, o con el
+/// código de error del sistema (o 1 si no tiene) si devuelve `Err`.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use concurrentes::process;
+///
+/// let mut child = process::spawn_fn(|| {
+///   println!("Child process");
+///   Ok(())
+/// }).unwrap();
+/// assert!(child.join().unwrap().success());
+/// ```
+pub fn spawn_fn<F>(f: F) -> Result<ChildHandle, Error> where F: FnOnce() -> Result<(), Error> {
+  match fork()? {
+    ForkResult::Parent{child} => {
+      Ok(ChildHandle {pid: child, status: None, drop_policy: DropPolicy::Detach})
+    },
+    ForkResult::Child => {
+      let code = match f() {
+        Ok(()) => 0,
+        Err(e) => e.raw_os_error().unwrap_or(1)
+      };
+      ::std::process::exit(code)
+    }
+  }
+}
+
+/// Configura la señal `signal` que recibirá el proceso actual cuando muera su
+/// padre (`prctl(PR_SET_PDEATHSIG)`). La configuración se mantiene al
+/// ejecutar otro programa, pero no se hereda con `fork`.
+///
+/// `parent` es el pid del padre esperado: si ya murió antes de la llamada,
+/// el proceso se envía la señal a sí mismo.
+pub fn set_parent_death_signal(signal: c_int, parent: pid_t) -> Result<(), Error> {
+  unsafe {
+    if libc::prctl(libc::PR_SET_PDEATHSIG, signal as libc::c_ulong) == -1 {
+      return Err(Error::last_os_error());
+    }
+    if libc::getppid() != parent && libc::kill(libc::getpid(), signal) == -1 {
+      return Err(Error::last_os_error());
+    }
+  }
+  Ok(())
+}
+
+/// Mueve al proceso actual al grupo `pgid`. Con 0, o si el grupo ya no
+/// existe, el proceso pasa a liderar un grupo nuevo.
+fn join_process_group(pgid: pid_t) -> Result<(), Error> {
+  unsafe {
+    if libc::setpgid(0, pgid) == -1 && libc::setpgid(0, 0) == -1 {
+      return Err(Error::last_os_error());
+    }
+  }
+  Ok(())
+}
+
+/// Crea una nueva sesión (y un nuevo grupo) liderada por el proceso actual,
+/// sin terminal de control. Devuelve el id de la sesión.
+pub fn setsid() -> Result<pid_t, Error> {
+  let sid = unsafe { libc::setsid() };
+  if sid == -1 {
+    Err(Error::last_os_error())
+  } else {
+    Ok(sid)
+  }
+}
+
+/// Convierte al proceso actual en un daemon: hace un doble `fork` (los
+/// procesos intermedios terminan con código 0), crea una nueva sesión sin
+/// terminal de control y redirige la entrada y salida estándar a `/dev/null`.
+///
+/// El directorio de trabajo no se modifica, ya que las rutas de los IPCs
+/// suelen ser relativas a él.
+pub fn daemonize() -> Result<(), Error> {
+  if let ForkResult::Parent{..} = fork()? {
+    ::std::process::exit(0);
+  }
+  setsid()?;
+  // El segundo fork garantiza que el daemon no pueda volver a tomar una terminal
+  if let ForkResult::Parent{..} = fork()? {
+    ::std::process::exit(0);
+  }
+  let dev_null = CString::new("/dev/null")?;
+  unsafe {
+    let fd = libc::open(dev_null.as_ptr(), libc::O_RDWR);
+    if fd == -1 {
+      return Err(Error::last_os_error());
+    }
+    for std_fd in &[libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+      if libc::dup2(fd, *std_fd) == -1 {
+        return Err(Error::last_os_error());
+      }
+    }
+    if fd > libc::STDERR_FILENO {
+      libc::close(fd);
+    }
+  }
+  Ok(())
+}
+
+/// Envía la señal `signal` a todos los procesos del grupo `pgid`
+pub fn killpg(pgid: pid_t, signal: c_int) -> Result<(), Error> {
+  if unsafe { libc::killpg(pgid, signal) } == -1 {
+    Err(Error::last_os_error())
+  } else {
+    Ok(())
+  }
+}
+
+/// Grupo de procesos
+///
+/// Agrupa a los procesos hijos en un grupo distinto al del padre, de forma
+/// que se les pueda enviar una señal a todos juntos con `signal`. El primer
+/// hijo agregado pasa a ser el líder del grupo.
+///
+/// Para evitar carreras, cada hijo se mueve al grupo tanto desde el padre
+/// (`add`) como desde el propio hijo (`join` luego de `fork`, o
+/// `spawn_options` para `spawn_with_options`).
+///
+/// # Example
+///
+/// ```rust, no_run
+/// extern crate concurrentes;
+/// extern crate libc;
+///
+/// use concurrentes::process;
+/// use concurrentes::process::{ForkResult, ProcessGroup};
+///
+/// # fn main() {
+///
+/// let mut group = ProcessGroup::new();
+/// for _ in 0..3 {
+///   match process::fork().unwrap() {
+///     ForkResult::Parent{child} => group.add(child).unwrap(),
+///     ForkResult::Child => {
+///       group.join().unwrap();
+///       loop {}
+///     }
+///   }
+/// }
+/// group.signal(libc::SIGTERM).unwrap();
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct ProcessGroup {
+  pgid: Option<pid_t>
+}
+
+impl ProcessGroup {
+  /// Grupo vacío. Se crea al agregar el primer hijo.
+  pub fn new() -> ProcessGroup {
+    ProcessGroup {pgid: None}
+  }
+
+  /// Id del grupo, si ya tiene algún hijo
+  pub fn id(&self) -> Option<pid_t> {
+    self.pgid
+  }
+
+  /// Mueve al hijo `child` al grupo (desde el padre)
+  pub fn add(&mut self, child: pid_t) -> Result<(), Error> {
+    let pgid = self.pgid.unwrap_or(child);
+    unsafe {
+      // EACCES: el hijo ya ejecutó otro programa, y entró al grupo por su cuenta.
+      // EPERM: el grupo ya no existe, el hijo pasa a ser el nuevo líder.
+      if libc::setpgid(child, pgid) == -1 {
+        let error = Error::last_os_error();
+        match error.raw_os_error() {
+          Some(libc::EACCES) => {},
+          Some(libc::EPERM) => {
+            libc::setpgid(child, child);
+          },
+          _ => return Err(error)
+        }
+      }
+    }
+    let child_pgid = unsafe { libc::getpgid(child) };
+    if child_pgid == -1 {
+      return Err(Error::last_os_error());
+    }
+    if self.pgid.is_none() || child_pgid == child {
+      self.pgid = Some(child_pgid);
+    }
+    Ok(())
+  }
+
+  /// Mueve al proceso actual al grupo. Debe llamarlo el hijo luego de `fork`.
+  pub fn join(&self) -> Result<(), Error> {
+    join_process_group(self.pgid.unwrap_or(0))
+  }
+
+  /// Opciones de `spawn_with_options` que mueven al nuevo hijo al grupo
+  pub fn spawn_options(&self) -> SpawnOptions {
+    let mut options = SpawnOptions::new();
+    options.process_group(self.pgid.unwrap_or(0));
+    options
+  }
+
+  /// Envía la señal `signal` a todos los procesos del grupo
+  pub fn signal(&self, signal: c_int) -> Result<(), Error> {
+    match self.pgid {
+      Some(pgid) => killpg(pgid, signal),
+      None => Ok(())
+    }
+  }
+}
+
 /// Espera a que el proceso con pid `child` termine la ejecución, y libera sus recursos.
 /// Se puede utilizar `process::ANYCHILD` para esperar a cualquier proceso.
-pub fn waitpid(child: pid_t) -> Result<pid_t, Error> {
+///
+/// Devuelve el pid del hijo unido junto con su estado de salida.
+pub fn waitpid(child: pid_t) -> Result<(pid_t, ExitStatus), Error> {
+  loop {
+    // Sin WNOHANG waitpid no devuelve 0
+    if let Some(result) = waitpid_with_options(child, 0)? {
+      return Ok(result);
+    }
+  }
+}
+
+/// Versión no bloqueante de `waitpid` (utiliza `WNOHANG`).
+///
+/// Devuelve `None` si ningún hijo que coincida con `child` terminó todavía.
+pub fn try_waitpid(child: pid_t) -> Result<Option<(pid_t, ExitStatus)>, Error> {
+  waitpid_with_options(child, WNOHANG)
+}
+
+/// Igual que `waitpid`, pero permite pasar opciones (`WUNTRACED`, `WCONTINUED`)
+/// para ser notificado también cuando el hijo se detiene o se reanuda.
+///
+/// Con `WNOHANG`, devuelve `None` si ningún hijo cambió de estado.
+pub fn waitpid_with_options(child: pid_t, options: c_int) -> Result<Option<(pid_t, ExitStatus)>, Error> {
   let pid;
+  let mut status: c_int = 0;
   unsafe {
-    pid = c_waitpid(child, ptr::null_mut(), 0);
+    pid = c_waitpid(child, &mut status, options);
+  }
+  if pid > 0 {
+    Ok(Some((pid, ExitStatus::from_raw(status))))
+  } else if pid == 0 {
+    Ok(None)
+  } else {
+    Err(Error::last_os_error())
+  }
+}
+
+/// Recursos consumidos por un proceso, según `struct rusage`
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ResourceUsage {
+  /// Tiempo de CPU en modo usuario
+  pub user_time: Duration,
+  /// Tiempo de CPU en modo kernel
+  pub system_time: Duration,
+  /// Máximo de memoria residente, en KB
+  pub max_rss_kb: i64,
+  /// Fallos de página resueltos sin acceder a disco
+  pub minor_faults: i64,
+  /// Fallos de página que requirieron acceder a disco
+  pub major_faults: i64,
+  /// Cambios de contexto voluntarios (por ejemplo, al bloquearse)
+  pub voluntary_switches: i64,
+  /// Cambios de contexto involuntarios (por fin de quantum)
+  pub involuntary_switches: i64
+}
+
+impl ResourceUsage {
+  /// Convierte la estructura de libc
+  pub fn from_raw(usage: &libc::rusage) -> ResourceUsage {
+    ResourceUsage {
+      user_time: timeval_to_duration(&usage.ru_utime),
+      system_time: timeval_to_duration(&usage.ru_stime),
+      max_rss_kb: usage.ru_maxrss,
+      minor_faults: usage.ru_minflt,
+      major_faults: usage.ru_majflt,
+      voluntary_switches: usage.ru_nvcsw,
+      involuntary_switches: usage.ru_nivcsw
+    }
+  }
+
+  /// Tiempo total de CPU (usuario + kernel)
+  pub fn cpu_time(&self) -> Duration {
+    self.user_time + self.system_time
+  }
+
+  /// Acumula el consumo de otro proceso: suma tiempos, fallos y cambios de
+  /// contexto, y conserva el máximo de memoria residente
+  pub fn accumulate(&mut self, other: &ResourceUsage) {
+    self.user_time += other.user_time;
+    self.system_time += other.system_time;
+    self.max_rss_kb = self.max_rss_kb.max(other.max_rss_kb);
+    self.minor_faults += other.minor_faults;
+    self.major_faults += other.major_faults;
+    self.voluntary_switches += other.voluntary_switches;
+    self.involuntary_switches += other.involuntary_switches;
   }
+}
+
+fn timeval_to_duration(time: &libc::timeval) -> Duration {
+  Duration::new(time.tv_sec as u64, (time.tv_usec * 1000) as u32)
+}
+
+/// Igual que `waitpid_with_options`, pero devuelve también los recursos
+/// consumidos por el hijo unido (`wait4`).
+pub fn wait4(child: pid_t, options: c_int) -> Result<Option<(pid_t, ExitStatus, ResourceUsage)>, Error> {
+  let mut status: c_int = 0;
+  let mut usage: libc::rusage = unsafe { mem::zeroed() };
+  let pid = unsafe { libc::wait4(child, &mut status, options, &mut usage) };
   if pid > 0 {
-    Ok(pid)
+    Ok(Some((pid, ExitStatus::from_raw(status), ResourceUsage::from_raw(&usage))))
+  } else if pid == 0 {
+    Ok(None)
   } else {
     Err(Error::last_os_error())
   }
-}
\ No newline at end of file
+}
+
+/// Versión no bloqueante de `wait4`. Devuelve `None` si ningún hijo que
+/// coincida con `child` terminó todavía.
+pub fn try_wait4(child: pid_t) -> Result<Option<(pid_t, ExitStatus, ResourceUsage)>, Error> {
+  wait4(child, WNOHANG)
+}
+
+/// Recursos consumidos por el proceso actual (`RUSAGE_SELF`) o por todos sus
+/// hijos ya unidos (`RUSAGE_CHILDREN`)
+pub fn getrusage(who: c_int) -> Result<ResourceUsage, Error> {
+  let mut usage: libc::rusage = unsafe { mem::zeroed() };
+  if unsafe { libc::getrusage(who, &mut usage) } == -1 {
+    Err(Error::last_os_error())
+  } else {
+    Ok(ResourceUsage::from_raw(&usage))
+  }
+}
+
+/// Evento de terminación de un proceso hijo, informado por `ChildReaper`
+#[derive(Copy, Clone, Debug)]
+pub struct ChildEvent {
+  pub pid: pid_t,
+  pub status: ExitStatus,
+  /// Recursos consumidos por el hijo
+  pub usage: ResourceUsage
+}
+
+/// Recolector de procesos hijos
+///
+/// Maneja la señal `SIGCHLD` marcando que hay hijos para unir. El proceso
+/// padre llama periódicamente a `poll` desde su ciclo principal, que une sin
+/// bloquearse a todos los hijos terminados (evitando que queden zombies) y
+/// los devuelve como eventos.
+///
+/// Los clones comparten la marca, por lo que puede registrarse un clon como
+/// manejador y consultar el original sin tomar ningún `Mutex`.
+#[derive(Clone, Default)]
+pub struct ChildReaper {
+  pending: Arc<AtomicBool>
+}
+
+impl SignalHandler for ChildReaper {
+  fn handle(&mut self) {
+    self.pending.store(true, Ordering::SeqCst);
+  }
+}
+
+impl ChildReaper {
+  /// Constructor default
+  pub fn new() -> ChildReaper {
+    ChildReaper {pending: Arc::new(AtomicBool::new(false))}
+  }
+
+  /// Crea un recolector y registra un clon como manejador de `SIGCHLD`
+  /// durante el resto del proceso. La señal se instala con `SA_RESTART`,
+  /// para que la terminación de un hijo no interrumpa las llamadas
+  /// bloqueantes del padre.
+  pub fn register() -> Result<ChildReaper, Error> {
+    let reaper = ChildReaper::new();
+    let handler = Arc::new(Mutex::new(reaper.clone()));
+    let options = *SignalOptions::new().restart(true);
+    SignalHandlerDispatcher::register_with_options(libc::SIGCHLD, handler, &options)?.keep();
+    Ok(reaper)
+  }
+
+  /// Une a todos los hijos que terminaron desde la última llamada y los
+  /// devuelve como eventos. Antes despacha las señales pendientes, de forma
+  /// de enterarse de los `SIGCHLD` recibidos. No se bloquea.
+  pub fn poll(&mut self) -> Result<Vec<ChildEvent>, Error> {
+    let mut events = Vec::new();
+    SignalHandlerDispatcher::dispatch_pending();
+    // Se limpia antes de unir, para no perder un SIGCHLD que llegue mientras tanto
+    if !self.pending.swap(false, Ordering::SeqCst) {
+      return Ok(events);
+    }
+    loop {
+      match try_wait4(ANY_CHILD) {
+        Ok(Some((pid, status, usage))) => events.push(ChildEvent {pid, status, usage}),
+        Ok(None) => break,
+        Err(ref e) if e.raw_os_error() == Some(libc::ECHILD) => break,
+        Err(e) => return Err(e)
+      }
+    }
+    Ok(events)
+  }
+}
@@ -1,82 +1,709 @@
-use std::rc::Rc;
-use std::cell::RefCell;
+use libc;
+use ipc::event_fd::{EventFd, EventFdMode};
+use std::io;
 use std::mem;
 use std::ptr;
+use std::os::unix::io::AsRawFd;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
 
-use libc::{sigaction, sigaddset, sigemptyset, sighandler_t};
-use libc::{c_void, alarm as c_alarm, kill as c_kill};
+use libc::{sigaction, sigaddset, sigemptyset, sighandler_t, siginfo_t, sigset_t};
+use libc::{c_int, c_void, pid_t, uid_t, alarm as c_alarm, kill as c_kill};
 
-thread_local! {
-  /// Vector global con handlers de señales
-  static SIG_REGISTER: RefCell<SignalHandlerDispatcher> = RefCell::new(SignalHandlerDispatcher {
-    handlers: vec![]
-  });
-}
+/// Registro global de handlers de señales, compartido por todos los hilos
+static SIG_REGISTER: Mutex<SignalHandlerDispatcher> = Mutex::new(SignalHandlerDispatcher {
+  handlers: Vec::new(),
+  previous: Vec::new(),
+  next_id: 0
+});
+
+/// Extremo de lectura del self-pipe donde se anotan las señales recibidas
+static PIPE_READ: AtomicI32 = AtomicI32::new(-1);
+/// Extremo de escritura del self-pipe, usado desde el handler de bajo nivel
+static PIPE_WRITE: AtomicI32 = AtomicI32::new(-1);
 
 /// Crea una alarma con tiempo `secs`. Si `secs` es 0, se desactiva.
 /// Si pasan la cantidad de segundos pasada por parámetro, se emite la señal
-/// `SIGALRM`
+/// `SIGALRM`. Para varios temporizadores o mayor resolución, ver `timer`.
 pub fn alarm(secs: u32) {
   unsafe {
     c_alarm(secs);
   }
 }
 
-pub fn signal(pid: i32, signal: i32) {
+/// Envía la señal `signal` al proceso `pid` (`kill`)
+pub fn signal(pid: i32, signal: i32) -> io::Result<()> {
+  if unsafe { c_kill(pid, signal) } == -1 {
+    Err(io::Error::last_os_error())
+  } else {
+    Ok(())
+  }
+}
+
+extern "C" {
+  fn sigqueue(pid: pid_t, sig: c_int, value: RawSigval) -> c_int;
+  fn __libc_current_sigrtmin() -> c_int;
+  fn __libc_current_sigrtmax() -> c_int;
+}
+
+/// Encola la señal `signal` con el valor `value` para el proceso `pid`
+/// (`sigqueue`). El receptor obtiene el valor en `SignalInfo::value`.
+///
+/// Las señales de tiempo real (ver `rt_signal`) se encolan: cada envío se
+/// entrega por separado y en orden. Las señales estándar, en cambio, se
+/// combinan si llegan varias antes de ser atendidas.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use concurrentes::signal;
+///
+/// let command = signal::rt_signal(0).unwrap();
+/// signal::queue(1234, command, 42).unwrap();
+/// ```
+pub fn queue(pid: pid_t, signal: c_int, value: c_int) -> io::Result<()> {
+  let mut sigval = RawSigval {sival_ptr: ptr::null_mut()};
+  sigval.sival_int = value;
+  if unsafe { sigqueue(pid, signal, sigval) } == -1 {
+    Err(io::Error::last_os_error())
+  } else {
+    Ok(())
+  }
+}
+
+/// Señal de tiempo real `SIGRTMIN + n`, o `None` si supera `SIGRTMAX`.
+///
+/// Los procesos que se comunican deben acordar el mismo `n`.
+pub fn rt_signal(n: c_int) -> Option<c_int> {
+  let (min, max) = unsafe { (__libc_current_sigrtmin(), __libc_current_sigrtmax()) };
+  if n >= 0 && min + n <= max {
+    Some(min + n)
+  } else {
+    None
+  }
+}
+
+/// Cantidad de señales de tiempo real disponibles para `rt_signal`
+pub fn rt_signal_count() -> c_int {
+  unsafe { __libc_current_sigrtmax() - __libc_current_sigrtmin() + 1 }
+}
+
+/// Qué hacer cuando una llamada bloqueante es interrumpida por una señal
+/// (`EINTR`). La usan `NamedPipeReader`, `Semaphore` y `FileLock`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum EintrPolicy {
+  /// Devolver el error `Interrupted`, para que quien llama atienda la señal
+  #[default]
+  Surface,
+  /// Reintentar la llamada
+  Retry
+}
+
+impl EintrPolicy {
+  /// Ejecuta `call` aplicando la política
+  pub fn apply<T, F: FnMut() -> io::Result<T>>(self, mut call: F) -> io::Result<T> {
+    loop {
+      match call() {
+        Err(ref e) if self == EintrPolicy::Retry && e.kind() == io::ErrorKind::Interrupted => {},
+        result => return result
+      }
+    }
+  }
+}
+
+/// Opciones con las que se instala una señal (`sa_flags` de `sigaction`):
+/// `SA_RESTART`, `SA_NODEFER` y `SA_RESETHAND`.
+///
+/// Por defecto no usa ningún flag: las llamadas bloqueantes interrumpidas
+/// por la señal fallan con `EINTR`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SignalOptions {
+  restart: bool,
+  no_defer: bool,
+  reset_handler: bool
+}
+
+impl SignalOptions {
+  /// Opciones por defecto
+  pub fn new() -> SignalOptions {
+    SignalOptions::default()
+  }
+
+  /// `SA_RESTART`: el sistema reanuda las llamadas interrumpidas (`read`,
+  /// `open`, `fcntl`, pero no `semop`) en lugar de fallar con `EINTR`
+  pub fn restart(&mut self, restart: bool) -> &mut SignalOptions {
+    self.restart = restart;
+    self
+  }
+
+  /// `SA_NODEFER`: no bloquea la señal mientras se la está anotando
+  pub fn no_defer(&mut self, no_defer: bool) -> &mut SignalOptions {
+    self.no_defer = no_defer;
+    self
+  }
+
+  /// `SA_RESETHAND`: tras la primera entrega vuelve a la acción por defecto
+  pub fn reset_handler(&mut self, reset_handler: bool) -> &mut SignalOptions {
+    self.reset_handler = reset_handler;
+    self
+  }
+
+  /// Flags para `sigaction`, que siempre incluyen `SA_SIGINFO`
+  fn flags(&self) -> c_int {
+    let mut flags = libc::SA_SIGINFO;
+    if self.restart {
+      flags |= libc::SA_RESTART;
+    }
+    if self.no_defer {
+      flags |= libc::SA_NODEFER;
+    }
+    if self.reset_handler {
+      flags |= libc::SA_RESETHAND;
+    }
+    flags
+  }
+}
+
+/// Bloquea un conjunto de señales mientras exista (`pthread_sigmask`).
+///
+/// Las señales bloqueadas que lleguen quedan pendientes y se entregan al
+/// destruirse la guarda, al restaurar la máscara anterior. Sirve para que
+/// una señal no interrumpa una sección crítica, como un intercambio con
+/// otro proceso por un FIFO.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// extern crate concurrentes;
+/// extern crate libc;
+///
+/// use concurrentes::signal::SignalMask;
+///
+/// fn main() {
+///   {
+///     let _mask = SignalMask::block(&[libc::SIGUSR1]).unwrap();
+///     // Un SIGUSR1 recibido aquí no interrumpe la sección crítica
+///   }
+///   // Y se entrega aquí
+/// }
+/// ```
+pub struct SignalMask {
+  previous: sigset_t
+}
+
+impl SignalMask {
+  /// Bloquea `signals`, además de las ya bloqueadas
+  pub fn block(signals: &[c_int]) -> io::Result<SignalMask> {
+    let set = signal_set(signals);
+    let mut previous: sigset_t = unsafe { mem::zeroed() };
+    let result = unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &set, &mut previous) };
+    if result != 0 {
+      Err(io::Error::from_raw_os_error(result))
+    } else {
+      Ok(SignalMask {previous})
+    }
+  }
+}
+
+impl Drop for SignalMask {
+  fn drop(&mut self) {
+    unsafe {
+      libc::pthread_sigmask(libc::SIG_SETMASK, &self.previous, ptr::null_mut());
+    }
+  }
+}
+
+/// Espera a recibir alguna de las señales `signals` (`sigtimedwait`), sin
+/// consumir CPU. Con `timeout` en `None` espera indefinidamente.
+///
+/// * Devuelve la información de la señal recibida, que no pasa por los
+///   manejadores registrados.
+/// * Devuelve `None` si se agotó el tiempo.
+/// * Si otra señal interrumpe la espera, devuelve un error `Interrupted`.
+///
+/// Para no perder una señal enviada antes de empezar a esperar (por
+/// ejemplo, la respuesta a un pedido), hay que bloquearla con `SignalMask`
+/// antes de hacer el pedido.
+pub fn wait_for(signals: &[c_int], timeout: Option<Duration>) -> io::Result<Option<SignalInfo>> {
+  let _mask = SignalMask::block(signals)?;
+  let set = signal_set(signals);
+  let mut info: siginfo_t = unsafe { mem::zeroed() };
+  let result = match timeout {
+    Some(timeout) => {
+      let timespec = libc::timespec {
+        tv_sec: timeout.as_secs() as libc::time_t,
+        tv_nsec: libc::c_long::from(timeout.subsec_nanos())
+      };
+      unsafe { libc::sigtimedwait(&set, &mut info, &timespec) }
+    },
+    None => unsafe { libc::sigwaitinfo(&set, &mut info) }
+  };
+  if result != -1 {
+    return Ok(Some(SignalInfo::from_raw(&info)));
+  }
+  let error = io::Error::last_os_error();
+  if error.raw_os_error() == Some(libc::EAGAIN) {
+    Ok(None)
+  } else {
+    Err(error)
+  }
+}
+
+/// Arma un `sigset_t` con las señales `signals`
+fn signal_set(signals: &[c_int]) -> sigset_t {
   unsafe {
-    c_kill(pid, signal);
+    let mut set: sigset_t = mem::zeroed();
+    sigemptyset(&mut set);
+    for signal in signals {
+      sigaddset(&mut set, *signal);
+    }
+    set
+  }
+}
+
+/// Información de una señal recibida, obtenida de `siginfo_t`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SignalInfo {
+  pub signal: c_int,
+  /// Pid del proceso que la envió (o del hijo, para `SIGCHLD`)
+  pub pid: pid_t,
+  /// Usuario real del proceso que la envió
+  pub uid: uid_t,
+  /// Origen de la señal (`SI_USER`, `SI_QUEUE`, `SI_KERNEL`, `CLD_EXITED`, etc)
+  pub code: c_int,
+  /// Valor adjunto con `sigqueue`, 0 si no tiene
+  pub value: c_int
+}
+
+/// Disposición de `siginfo_t` para las señales enviadas por un proceso
+/// (`kill`, `sigqueue`, `SIGCHLD`), con la que se leen `si_pid`, `si_uid` y
+/// el valor adjunto como entero (`libc` sólo lo expone como puntero).
+#[repr(C)]
+struct RawSigInfo {
+  si_signo: c_int,
+  si_errno: c_int,
+  si_code: c_int,
+  fields: RawSigFields
+}
+
+#[repr(C)]
+struct RawSigFields {
+  si_pid: pid_t,
+  si_uid: uid_t,
+  si_value: RawSigval
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+union RawSigval {
+  sival_int: c_int,
+  sival_ptr: *mut c_void
+}
+
+impl SignalInfo {
+  /// Convierte el `siginfo_t` recibido por un handler `SA_SIGINFO`
+  fn from_raw(info: &siginfo_t) -> SignalInfo {
+    let raw = unsafe { &*(info as *const siginfo_t as *const RawSigInfo) };
+    SignalInfo {
+      signal: raw.si_signo,
+      pid: raw.fields.si_pid,
+      uid: raw.fields.si_uid,
+      code: raw.si_code,
+      value: unsafe { raw.fields.si_value.sival_int }
+    }
   }
 }
 
 /// Interfaz para todos los manejadores de señales
+///
+/// `handle` no se ejecuta dentro del handler de la señal sino desde
+/// `SignalHandlerDispatcher::dispatch_pending`, por lo que puede usar
+/// cualquier código (alocar memoria, loguear, tomar un `Mutex`). Puede
+/// ejecutarse en un hilo distinto al que lo registró, por lo que debe ser
+/// `Send`.
 pub trait SignalHandler {
   fn handle(&mut self);
+
+  /// Igual que `handle`, pero recibe quién envió la señal y el valor
+  /// adjunto. Por defecto llama a `handle`.
+  fn handle_info(&mut self, _info: &SignalInfo) {
+    self.handle();
+  }
 }
 
-/// Manejador por defector (Nulo)
-struct NullSignalHandler {}
+/// Manejador compartido entre quien lo registra y el despachador
+pub type SharedHandler = Arc<Mutex<SignalHandler + Send>>;
 
-impl SignalHandler for NullSignalHandler {
-  fn handle(&mut self) {}
-}
+/// Manejadores de una señal, junto al identificador de su registro
+type HandlerChain = Vec<(usize, SharedHandler)>;
 
 /// Despachador de manejadores
+///
+/// Las señales se instalan con `SA_SIGINFO`. Al recibir una señal
+/// registrada, el handler de bajo nivel sólo anota su `SignalInfo` en un
+/// pipe no bloqueante (self-pipe), que es async-signal-safe.
+/// Los `SignalHandler` se ejecutan recién cuando el ciclo principal llama a
+/// `dispatch_pending`, en un punto seguro del programa.
+///
+/// El registro es único para todo el proceso: los manejadores pueden
+/// registrarse, quitarse y despacharse desde cualquier hilo, sin importar
+/// cuál recibió la señal. Para atender las señales en un hilo aparte, en
+/// lugar de hacerlo desde el ciclo principal, ver `SignalThread`.
+///
+/// Una misma señal puede tener varios manejadores encadenados, que se
+/// ejecutan en el orden en que se registraron. Cada registro devuelve un
+/// `SignalRegistration`; al destruirlo se quita el manejador, y al quitar
+/// el último se restaura la acción que tenía la señal antes de registrarla.
+///
+/// Como la señal se sigue instalando sin `SA_RESTART`, las llamadas
+/// bloqueantes interrumpidas devuelven `EINTR`; quien la reciba debe llamar
+/// a `dispatch_pending` antes de consultar el estado de sus manejadores.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// extern crate concurrentes;
+/// extern crate libc;
+///
+/// use concurrentes::signal::{SignalHandler, SignalHandlerDispatcher};
+/// use std::sync::{Arc, Mutex};
+///
+/// struct Flag(bool);
+///
+/// impl SignalHandler for Flag {
+///   fn handle(&mut self) {
+///     self.0 = true;
+///   }
+/// }
+///
+/// fn main() {
+///   let flag = Arc::new(Mutex::new(Flag(false)));
+///   let _registration = SignalHandlerDispatcher::register(libc::SIGINT, flag.clone()).unwrap();
+///   while !flag.lock().unwrap().0 {
+///     std::thread::sleep(std::time::Duration::from_millis(100));
+///     SignalHandlerDispatcher::dispatch_pending();
+///   }
+/// }
+/// ```
 pub struct SignalHandlerDispatcher {
-  /// Vector con las distitnas instancias de SignalHandler
-  handlers: Vec<Rc<RefCell<SignalHandler>>>
+  /// Manejadores encadenados de cada señal (índice `signum - 1`)
+  handlers: Vec<HandlerChain>,
+  /// Acción previa de cada señal, restaurada al quitar su último manejador
+  previous: Vec<Option<sigaction>>,
+  /// Identificador del próximo registro
+  next_id: usize
+}
+
+/// Registro de un manejador de señales, devuelto por
+/// `SignalHandlerDispatcher::register`. Al destruirse quita el manejador.
+#[must_use = "el manejador se desregistra al destruir el SignalRegistration"]
+#[derive(Debug)]
+pub struct SignalRegistration {
+  signum: c_int,
+  id: usize
+}
+
+impl SignalRegistration {
+  /// Señal a la que corresponde el registro
+  pub fn signal(&self) -> c_int {
+    self.signum
+  }
+
+  /// Quita el manejador. Si era el último de la señal, restaura la acción
+  /// previa y devuelve el error de `sigaction`, si lo hubo.
+  pub fn unregister(self) -> io::Result<()> {
+    let result = SignalHandlerDispatcher::remove(self.signum, self.id);
+    mem::forget(self);
+    result
+  }
+
+  /// Mantiene el manejador registrado durante el resto del proceso
+  pub fn keep(self) {
+    mem::forget(self);
+  }
 }
 
+impl Drop for SignalRegistration {
+  fn drop(&mut self) {
+    let _ = SignalHandlerDispatcher::remove(self.signum, self.id);
+  }
+}
 
 impl SignalHandlerDispatcher {
-  /// Registra un manejador de señales
-  pub fn register(signum: i32, handler: Rc<RefCell<SignalHandler>>) {
-    let _signum = signum as usize;
-    SIG_REGISTER.with(|cell| {
-      let mut dispatcher = cell.borrow_mut();
-      let handler_vec = &mut dispatcher.handlers;
-      if handler_vec.len() < _signum {
-        let default_handler = Rc::new(RefCell::new(NullSignalHandler{}));
-        handler_vec.resize(_signum, default_handler);
+  /// Registra un manejador de señales, encadenándolo a los que ya tenga
+  /// `signum`. Queda registrado mientras exista el `SignalRegistration`.
+  ///
+  /// Falla con `InvalidInput` si `signum` no es una señal válida, o con el
+  /// error de `sigaction` si no se pudo instalar; en ese caso el manejador
+  /// no queda registrado.
+  pub fn register(signum: i32, handler: SharedHandler) -> io::Result<SignalRegistration> {
+    SignalHandlerDispatcher::register_with_options(signum, handler, &SignalOptions::new())
+  }
+
+  /// Igual que `register`, instalando la señal con `options`. Si la señal ya
+  /// tenía manejadores, pasa a usar las opciones de este último registro.
+  pub fn register_with_options(signum: i32, handler: SharedHandler,
+      options: &SignalOptions) -> io::Result<SignalRegistration> {
+    if signum < 1 || signum > libc::SIGRTMAX() {
+      let msg = format!("Señal inválida: {}", signum);
+      return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+    }
+    let index = signum as usize - 1;
+    // El registro queda tomado hasta instalar la señal, para que otro hilo
+    // no la instale ni la restaure mientras tanto
+    let mut dispatcher = registry();
+    open_pipe();
+    if dispatcher.handlers.len() <= index {
+      dispatcher.handlers.resize(index + 1, Vec::new());
+      dispatcher.previous.resize(index + 1, None);
+    }
+    let id = dispatcher.next_id;
+    dispatcher.next_id += 1;
+    let first = dispatcher.handlers[index].is_empty();
+    dispatcher.handlers[index].push((id, handler));
+    let previous = unsafe {
+      let mut sa : sigaction = mem::zeroed();
+      let mut previous : sigaction = mem::zeroed();
+      sa.sa_sigaction = SignalHandlerDispatcher::record as *mut c_void as sighandler_t;
+      sa.sa_flags = options.flags();
+      sigemptyset(&mut sa.sa_mask);
+      if !options.no_defer {
+        sigaddset(&mut sa.sa_mask, signum);
+      }
+      if sigaction(signum, &sa, &mut previous) == 0 {
+        Ok(previous)
+      } else {
+        Err(io::Error::last_os_error())
+      }
+    };
+    let previous = match previous {
+      Ok(previous) => previous,
+      Err(e) => {
+        dispatcher.handlers[index].pop();
+        return Err(e);
+      }
+    };
+    // Con el primer manejador se guarda la acción previa, para restaurarla
+    if first {
+      dispatcher.previous[index] = Some(previous);
+    }
+    Ok(SignalRegistration {signum, id})
+  }
+
+  /// Ejecuta `handle_info()` (método del Trait `SignalHandler`) de cada
+  /// manejador, para cada señal recibida desde la última llamada, en el
+  /// orden en que llegaron.
+  /// Devuelve la cantidad de señales despachadas. No se bloquea.
+  ///
+  /// Toma el `Mutex` de cada manejador mientras lo ejecuta, por lo que no
+  /// debe llamarse teniendo tomado alguno de ellos.
+  pub fn dispatch_pending() -> usize {
+    let mut dispatched = 0;
+    while let Some(info) = read_pending() {
+      // Suelto el registro antes de llamar a los handlers, que podrían
+      // registrar o quitar otros
+      let chain: Vec<SharedHandler> = registry().handlers.get(info.signal as usize - 1)
+        .map(|chain| chain.iter().map(|(_, handler)| handler.clone()).collect())
+        .unwrap_or_default();
+      for handler in &chain {
+        // Un handler que entró en pánico en otro hilo se sigue ejecutando
+        let mut handler = handler.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        handler.handle_info(&info);
+      }
+      if !chain.is_empty() {
+        dispatched += 1;
       }
-      handler_vec[_signum - 1] = handler;
-    });
+    }
+    dispatched
+  }
+
+  /// Quita el manejador registrado con `id`. Si era el último de `signum`,
+  /// restaura la acción previa de la señal.
+  fn remove(signum: c_int, id: usize) -> io::Result<()> {
+    let index = signum as usize - 1;
+    let mut dispatcher = registry();
+    let position = dispatcher.handlers.get(index)
+      .and_then(|chain| chain.iter().position(|&(other, _)| other == id));
+    let handler = position.map(|position| dispatcher.handlers[index].remove(position));
+    let previous = if dispatcher.handlers.get(index).is_some_and(|chain| chain.is_empty()) {
+      dispatcher.previous[index].take()
+    } else {
+      None
+    };
+    // La acción previa se restaura con el registro tomado, igual que al
+    // instalarla; el manejador se destruye después de soltarlo
+    let result = match previous {
+      Some(previous) if unsafe { sigaction(signum, &previous, ptr::null_mut()) } == -1 =>
+        Err(io::Error::last_os_error()),
+      _ => Ok(())
+    };
+    drop(dispatcher);
+    drop(handler);
+    result
+  }
+
+  /// Función llamada al recibir una señal. Sólo anota su `SignalInfo` en
+  /// el self-pipe, preservando `errno`.
+  extern "C" fn record(_signum: c_int, info: *mut siginfo_t, _context: *mut c_void) {
+    let fd = PIPE_WRITE.load(Ordering::SeqCst);
+    if fd < 0 || info.is_null() {
+      return;
+    }
     unsafe {
-      let mut sa : sigaction = mem::uninitialized();
-      sa.sa_sigaction = SignalHandlerDispatcher::dispatch as *mut c_void as sighandler_t;
-      sa.sa_flags = 0;
-      sigemptyset(&mut sa.sa_mask);
-      sigaddset(&mut sa.sa_mask, signum);
-      sigaction(signum, &sa, ptr::null_mut());
+      let errno = *libc::__errno_location();
+      let info = SignalInfo::from_raw(&*info);
+      // Si el pipe está lleno la señal se pierde, igual que al coalescer señales
+      libc::write(fd, &info as *const SignalInfo as *const c_void, mem::size_of::<SignalInfo>());
+      *libc::__errno_location() = errno;
+    }
+  }
+}
+
+/// Toma el registro global. Si un hilo entró en pánico teniéndolo tomado,
+/// se sigue usando: sus operaciones no lo dejan inconsistente.
+fn registry() -> MutexGuard<'static, SignalHandlerDispatcher> {
+  SIG_REGISTER.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Crea el self-pipe si todavía no existe. Se llama con el registro tomado,
+/// para que dos hilos no lo creen a la vez.
+fn open_pipe() {
+  if PIPE_WRITE.load(Ordering::SeqCst) >= 0 {
+    return;
+  }
+  let mut fds = [-1; 2];
+  if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) } == 0 {
+    PIPE_READ.store(fds[0], Ordering::SeqCst);
+    PIPE_WRITE.store(fds[1], Ordering::SeqCst);
+  }
+}
+
+/// Reemplaza el self-pipe heredado por uno propio. Lo llama `process::fork`
+/// en el hijo, para que padre e hijo no se roben las señales. El hijo tiene
+/// un único hilo, por lo que no hace falta tomar el registro.
+pub(crate) fn reopen_after_fork() {
+  let read = PIPE_READ.swap(-1, Ordering::SeqCst);
+  let write = PIPE_WRITE.swap(-1, Ordering::SeqCst);
+  if write < 0 {
+    return;
+  }
+  unsafe {
+    libc::close(read);
+    libc::close(write);
+  }
+  open_pipe();
+}
+
+/// Lee del self-pipe la próxima señal pendiente, si hay alguna
+fn read_pending() -> Option<SignalInfo> {
+  let fd = PIPE_READ.load(Ordering::SeqCst);
+  if fd < 0 {
+    return None;
+  }
+  let mut info: SignalInfo = unsafe { mem::zeroed() };
+  let size = mem::size_of::<SignalInfo>();
+  let read = unsafe { libc::read(fd, &mut info as *mut SignalInfo as *mut c_void, size) };
+  if read == size as isize {
+    Some(info)
+  } else {
+    None
+  }
+}
+
+/// Hilo dedicado a atender señales
+///
+/// Al crearlo, bloquea las señales `signals` en el hilo que lo crea (y por
+/// lo tanto en los hilos que éste cree después), y las desbloquea sólo en
+/// el hilo dedicado. Así el kernel le entrega a él las señales, sin
+/// interrumpir las llamadas bloqueantes del resto, y es él quien ejecuta
+/// los manejadores registrados con `SignalHandlerDispatcher`, apenas llegan.
+///
+/// Al destruirse detiene el hilo y restaura la máscara del hilo que lo
+/// destruye, que debería ser el mismo que lo creó. No sobrevive a un
+/// `process::fork`: el hijo debe crear el suyo.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// extern crate concurrentes;
+/// extern crate libc;
+///
+/// use concurrentes::signal::{SignalHandler, SignalHandlerDispatcher, SignalThread};
+/// use std::sync::{Arc, Mutex};
+///
+/// struct Flag(bool);
+///
+/// impl SignalHandler for Flag {
+///   fn handle(&mut self) {
+///     self.0 = true;
+///   }
+/// }
+///
+/// fn main() {
+///   let flag = Arc::new(Mutex::new(Flag(false)));
+///   let _registration = SignalHandlerDispatcher::register(libc::SIGINT, flag.clone()).unwrap();
+///   let _signal_thread = SignalThread::spawn(&[libc::SIGINT]).unwrap();
+///   // No hace falta llamar a dispatch_pending
+///   while !flag.lock().unwrap().0 {
+///     std::thread::sleep(std::time::Duration::from_millis(100));
+///   }
+/// }
+/// ```
+pub struct SignalThread {
+  stop: Arc<EventFd>,
+  thread: Option<JoinHandle<()>>,
+  _mask: SignalMask
+}
+
+impl SignalThread {
+  /// Crea el hilo dedicado a las señales `signals`
+  pub fn spawn(signals: &[c_int]) -> io::Result<SignalThread> {
+    // El self-pipe se crea con el registro tomado, como al registrar
+    {
+      let _dispatcher = registry();
+      open_pipe();
+    }
+    let stop = Arc::new(EventFd::new(0, EventFdMode::Counter)?);
+    let mask = SignalMask::block(signals)?;
+    let unblocked = signal_set(signals);
+    let thread_stop = stop.clone();
+    let thread = thread::Builder::new().name("signals".to_string()).spawn(move || {
+      unsafe {
+        libc::pthread_sigmask(libc::SIG_UNBLOCK, &unblocked, ptr::null_mut());
+      }
+      SignalThread::run(&thread_stop);
+    })?;
+    Ok(SignalThread {stop, thread: Some(thread), _mask: mask})
+  }
+
+  /// Despacha las señales a medida que se anotan en el self-pipe, hasta que
+  /// se notifique `stop`
+  fn run(stop: &EventFd) {
+    loop {
+      let mut poll_fds = [
+        libc::pollfd {fd: PIPE_READ.load(Ordering::SeqCst), events: libc::POLLIN, revents: 0},
+        libc::pollfd {fd: stop.as_raw_fd(), events: libc::POLLIN, revents: 0}
+      ];
+      let result = unsafe { libc::poll(poll_fds.as_mut_ptr(), 2, -1) };
+      if result == -1 && io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+        return;
+      }
+      if poll_fds[1].revents != 0 {
+        return;
+      }
+      SignalHandlerDispatcher::dispatch_pending();
     }
   }
+}
 
-  /// Función llamada al recibir una señal. Busca el handler correspondiente
-  /// a `signum` y ejecuta `handle()` (método del Trait `SignalHandler`)
-  fn dispatch(signum: i32) {
-    SIG_REGISTER.with(|cell| {
-      let mut dispatcher = cell.borrow_mut();
-      let handler_vec = &mut dispatcher.handlers;
-      handler_vec[signum as usize - 1].borrow_mut().handle();
-    });
+impl Drop for SignalThread {
+  fn drop(&mut self) {
+    let _ = self.stop.notify();
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
   }
 }
@@ -0,0 +1,196 @@
+use libc::pid_t;
+use log::{GLOBAL_LOG, LogSeverity};
+use process;
+use process::{ExitStatus, SpawnOptions};
+
+use std::collections::VecDeque;
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Política de reinicio de un proceso supervisado
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RestartPolicy {
+  /// No se reinicia nunca
+  Never,
+  /// Se reinicia sólo si no terminó con código 0
+  OnFailure,
+  /// Se reinicia siempre que termine
+  Always
+}
+
+impl RestartPolicy {
+  /// Indica si un proceso que terminó con `status` debe reiniciarse
+  pub fn should_restart(&self, status: ExitStatus) -> bool {
+    match *self {
+      RestartPolicy::Never => false,
+      RestartPolicy::OnFailure => !status.success(),
+      RestartPolicy::Always => true
+    }
+  }
+}
+
+/// Especificación de un proceso supervisado: qué programa ejecutar, con qué
+/// argumentos y cuándo reiniciarlo.
+///
+/// Los argumentos se agregan uno a uno con `arg`, y la política de reinicio
+/// se elige con `policy`.
+#[derive(Clone, Debug)]
+pub struct ChildSpec {
+  name: String,
+  program: String,
+  args: Vec<String>,
+  policy: RestartPolicy
+}
+
+impl ChildSpec {
+  /// Especificación del programa `program`, identificado en el log como
+  /// `name`. Por defecto no recibe argumentos y se reinicia si falla.
+  pub fn new(name: &str, program: &str) -> ChildSpec {
+    ChildSpec {name: name.to_string(), program: program.to_string(), args: Vec::new(),
+      policy: RestartPolicy::OnFailure}
+  }
+
+  /// Agrega un argumento al programa
+  pub fn arg(&mut self, arg: &str) -> &mut ChildSpec {
+    self.args.push(arg.to_string());
+    self
+  }
+
+  /// Política de reinicio
+  pub fn policy(&mut self, policy: RestartPolicy) -> &mut ChildSpec {
+    self.policy = policy;
+    self
+  }
+
+  /// Nombre con el que se identifica al proceso
+  pub fn get_name(&self) -> &str {
+    &self.name
+  }
+
+  /// Lanza el programa con `process::spawn_with_options`
+  fn spawn(&self, options: &SpawnOptions) -> io::Result<pid_t> {
+    let args: Vec<&str> = self.args.iter().map(|arg| arg.as_str()).collect();
+    process::spawn_with_options(&self.program, &args, &[], options)
+  }
+}
+
+/// Qué hizo el supervisor ante la terminación de un proceso
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ExitAction {
+  /// El proceso no pertenece al supervisor
+  Unknown,
+  /// El proceso fue reiniciado con el nuevo pid
+  Restarted(pid_t),
+  /// El proceso terminó y su política no pide reiniciarlo
+  Finished,
+  /// El proceso superó el máximo de reinicios y no se volverá a lanzar
+  GaveUp
+}
+
+/// Proceso supervisado junto a su historial de reinicios
+struct Supervised {
+  spec: ChildSpec,
+  pid: Option<pid_t>,
+  restarts: VecDeque<Instant>
+}
+
+/// Supervisor de procesos
+///
+/// Lanza un conjunto de procesos (`ChildSpec`) y, al ser informado de su
+/// terminación (por ejemplo con los eventos de `process::ChildReaper`), los
+/// reinicia según su política. Cada proceso puede reiniciarse a lo sumo
+/// `max_restarts` veces dentro de una ventana de tiempo `window`; si se
+/// supera ese límite, el supervisor deja de relanzarlo.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use concurrentes::process::ChildReaper;
+/// use concurrentes::supervisor::{ChildSpec, RestartPolicy, Supervisor};
+/// use std::time::Duration;
+///
+/// let mut reaper = ChildReaper::register().unwrap();
+/// let mut supervisor = Supervisor::new(3, Duration::from_secs(10));
+/// supervisor.add(ChildSpec::new("sleeper", "sleep").arg("1").policy(RestartPolicy::Always))
+///   .unwrap();
+/// loop {
+///   for event in reaper.poll().unwrap() {
+///     supervisor.handle_exit(event.pid, event.status).unwrap();
+///   }
+/// }
+/// ```
+pub struct Supervisor {
+  children: Vec<Supervised>,
+  max_restarts: usize,
+  window: Duration,
+  options: SpawnOptions
+}
+
+impl Supervisor {
+  /// Crea un supervisor sin procesos
+  pub fn new(max_restarts: usize, window: Duration) -> Supervisor {
+    Supervisor {children: Vec::new(), max_restarts, window, options: SpawnOptions::new()}
+  }
+
+  /// Opciones con las que se lanzan (y relanzan) los procesos de aquí en más,
+  /// por ejemplo para ubicarlos en un `process::ProcessGroup`
+  pub fn set_spawn_options(&mut self, options: SpawnOptions) {
+    self.options = options;
+  }
+
+  /// Lanza el proceso descripto por `spec` y lo supervisa. Devuelve su pid.
+  pub fn add(&mut self, spec: &ChildSpec) -> io::Result<pid_t> {
+    let pid = spec.spawn(&self.options)?;
+    let msg = format!("Supervisando {} (pid {})", spec.name, pid);
+    log!(msg.as_str(), &LogSeverity::INFO);
+    self.children.push(Supervised {spec: spec.clone(), pid: Some(pid), restarts: VecDeque::new()});
+    Ok(pid)
+  }
+
+  /// Indica si el proceso `pid` es supervisado
+  pub fn owns(&self, pid: pid_t) -> bool {
+    self.children.iter().any(|child| child.pid == Some(pid))
+  }
+
+  /// Pids de los procesos supervisados que están corriendo
+  pub fn pids(&self) -> Vec<pid_t> {
+    self.children.iter().filter_map(|child| child.pid).collect()
+  }
+
+  /// Informa al supervisor que el proceso `pid` terminó con `status`, y lo
+  /// reinicia si corresponde
+  pub fn handle_exit(&mut self, pid: pid_t, status: ExitStatus) -> io::Result<ExitAction> {
+    if !status.is_terminated() {
+      return Ok(ExitAction::Unknown);
+    }
+    let (max_restarts, window, options) = (self.max_restarts, self.window, self.options);
+    let child = match self.children.iter_mut().find(|child| child.pid == Some(pid)) {
+      Some(child) => child,
+      None => return Ok(ExitAction::Unknown)
+    };
+    child.pid = None;
+    if !child.spec.policy.should_restart(status) {
+      let msg = format!("{} (pid {}) {}, no se reinicia", child.spec.name, pid, status);
+      log!(msg.as_str(), &LogSeverity::INFO);
+      return Ok(ExitAction::Finished);
+    }
+    // Descarto los reinicios que quedaron fuera de la ventana
+    let now = Instant::now();
+    while child.restarts.front().is_some_and(|start| now.duration_since(*start) > window) {
+      child.restarts.pop_front();
+    }
+    if child.restarts.len() >= max_restarts {
+      let msg = format!("{} (pid {}) {}, superó los {} reinicios en {:?}",
+        child.spec.name, pid, status, max_restarts, window);
+      log!(msg.as_str(), &LogSeverity::ERROR);
+      return Ok(ExitAction::GaveUp);
+    }
+    let new_pid = child.spec.spawn(&options)?;
+    child.restarts.push_back(now);
+    child.pid = Some(new_pid);
+    let msg = format!("{} (pid {}) {}, reiniciado con pid {}",
+      child.spec.name, pid, status, new_pid);
+    log!(msg.as_str(), &LogSeverity::WARN);
+    Ok(ExitAction::Restarted(new_pid))
+  }
+}
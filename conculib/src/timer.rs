@@ -0,0 +1,286 @@
+use libc;
+use libc::{c_int, c_void};
+use std::io;
+use std::io::Error;
+use std::mem;
+use std::ops::Drop;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::time::Duration;
+
+/// Modos de un temporizador
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TimerMode {
+  /// Vence una única vez
+  OneShot,
+  /// Vence repetidamente, cada vez que pasa el tiempo indicado
+  Periodic
+}
+
+/// Temporizador de alta resolución basado en `timerfd`
+///
+/// A diferencia de `signal::alarm`, tiene resolución de milisegundos, un
+/// proceso puede tener varios al mismo tiempo, y sus vencimientos no se
+/// entregan como `SIGALRM` sino que se leen de un file descriptor. Por eso
+/// mismo puede esperarse junto a otro descriptor, como un FIFO, con
+/// `wait_readable`.
+///
+/// Usa el reloj `CLOCK_MONOTONIC`, por lo que no lo afectan los cambios de
+/// hora del sistema.
+///
+/// # Example
+///
+/// ```rust
+/// use concurrentes::timer::{Timer, TimerMode};
+/// use std::time::Duration;
+///
+/// let mut timer = Timer::new().unwrap();
+/// timer.start(Duration::from_millis(10), TimerMode::OneShot).unwrap();
+/// assert!(timer.poll(1000).unwrap());
+/// assert_eq!(timer.expirations().unwrap(), 1);
+/// ```
+pub struct Timer {
+  fd: c_int
+}
+
+impl Timer {
+  /// Crea un temporizador detenido
+  pub fn new() -> io::Result<Timer> {
+    let fd;
+    unsafe {
+      fd = libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_NONBLOCK | libc::TFD_CLOEXEC);
+    }
+    if fd != -1 {
+      Ok(Timer{fd})
+    } else {
+      Err(Error::last_os_error())
+    }
+  }
+
+  /// Arma el temporizador para que venza dentro de `after`. En modo
+  /// `Periodic` vuelve a vencer cada `after`. Si ya estaba armado, lo
+  /// reprograma y descarta los vencimientos sin leer.
+  pub fn start(&mut self, after: Duration, mode: TimerMode) -> io::Result<()> {
+    // Un valor de 0 desarmaría el temporizador
+    let value = if after == Duration::from_secs(0) { Duration::new(0, 1) } else { after };
+    let interval = match mode {
+      TimerMode::OneShot => Duration::from_secs(0),
+      TimerMode::Periodic => value
+    };
+    self.set_time(value, interval)
+  }
+
+  /// Detiene el temporizador
+  pub fn stop(&mut self) -> io::Result<()> {
+    self.set_time(Duration::from_secs(0), Duration::from_secs(0))
+  }
+
+  /// Tiempo que falta para el próximo vencimiento, o `None` si está detenido
+  pub fn remaining(&self) -> io::Result<Option<Duration>> {
+    let mut spec: libc::itimerspec = unsafe { mem::zeroed() };
+    let result;
+    unsafe {
+      result = libc::timerfd_gettime(self.fd, &mut spec);
+    }
+    if result == -1 {
+      return Err(Error::last_os_error());
+    }
+    let remaining = Duration::new(spec.it_value.tv_sec as u64, spec.it_value.tv_nsec as u32);
+    if remaining == Duration::from_secs(0) {
+      Ok(None)
+    } else {
+      Ok(Some(remaining))
+    }
+  }
+
+  /// Cantidad de vencimientos desde la última lectura. No se bloquea:
+  /// devuelve 0 si todavía no venció.
+  pub fn expirations(&self) -> io::Result<u64> {
+    let mut value: u64 = 0;
+    let result;
+    unsafe {
+      result = libc::read(self.fd, &mut value as *mut u64 as *mut c_void, mem::size_of::<u64>());
+    }
+    if result >= 0 {
+      Ok(value)
+    } else {
+      let error = Error::last_os_error();
+      if error.raw_os_error() == Some(libc::EAGAIN) {
+        Ok(0)
+      } else {
+        Err(error)
+      }
+    }
+  }
+
+  /// Espera hasta `timeout_ms` milisegundos a que venza el temporizador
+  /// (-1 espera indefinidamente). Devuelve `true` si venció, sin consumir
+  /// el vencimiento.
+  pub fn poll(&self, timeout_ms: i32) -> io::Result<bool> {
+    let mut poll_fds = [libc::pollfd {fd: self.fd, events: libc::POLLIN, revents: 0}];
+    poll_all(&mut poll_fds, timeout_ms)?;
+    Ok(poll_fds[0].revents & libc::POLLIN != 0)
+  }
+
+  /// Espera a que `fd` tenga datos para leer o a que venza el temporizador,
+  /// lo que ocurra primero. Devuelve `true` si `fd` está listo (o su otro
+  /// extremo se cerró) y `false` si venció el temporizador.
+  pub fn wait_readable(&self, fd: RawFd) -> io::Result<bool> {
+    let mut poll_fds = [
+      libc::pollfd {fd, events: libc::POLLIN, revents: 0},
+      libc::pollfd {fd: self.fd, events: libc::POLLIN, revents: 0}
+    ];
+    poll_all(&mut poll_fds, -1)?;
+    Ok(poll_fds[0].revents & (libc::POLLIN | libc::POLLHUP) != 0)
+  }
+
+  fn set_time(&mut self, value: Duration, interval: Duration) -> io::Result<()> {
+    let spec = libc::itimerspec {
+      it_interval: timespec(interval),
+      it_value: timespec(value)
+    };
+    let result;
+    unsafe {
+      result = libc::timerfd_settime(self.fd, 0, &spec, ::std::ptr::null_mut());
+    }
+    if result != -1 {
+      Ok(())
+    } else {
+      Err(Error::last_os_error())
+    }
+  }
+}
+
+impl AsRawFd for Timer {
+  fn as_raw_fd(&self) -> RawFd {
+    self.fd
+  }
+}
+
+impl Drop for Timer {
+  /// Destructor: cierra el fd al salir
+  fn drop(&mut self) {
+    unsafe {
+      libc::close(self.fd);
+    }
+  }
+}
+
+/// Vencimiento de un temporizador de `Timers`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimerEvent {
+  /// Nombre del temporizador
+  pub name: String,
+  /// Cantidad de vencimientos desde la última consulta
+  pub expirations: u64
+}
+
+/// Conjunto de temporizadores independientes, identificados por nombre
+///
+/// El ciclo principal consulta los vencimientos con `poll` (sin bloquearse)
+/// o los espera con `wait`, y los recibe como eventos.
+///
+/// # Example
+///
+/// ```rust
+/// use concurrentes::timer::{Timers, TimerMode};
+/// use std::time::Duration;
+///
+/// let mut timers = Timers::new();
+/// timers.start("latido", Duration::from_millis(5), TimerMode::Periodic).unwrap();
+/// timers.start("fin", Duration::from_millis(50), TimerMode::OneShot).unwrap();
+/// loop {
+///   let events = timers.wait(None).unwrap();
+///   if events.iter().any(|event| event.name == "fin") {
+///     break;
+///   }
+/// }
+/// ```
+#[derive(Default)]
+pub struct Timers {
+  timers: Vec<(String, Timer)>
+}
+
+impl Timers {
+  /// Crea un conjunto vacío
+  pub fn new() -> Timers {
+    Timers {timers: Vec::new()}
+  }
+
+  /// Arma el temporizador `name`, creándolo si no existe
+  pub fn start(&mut self, name: &str, after: Duration, mode: TimerMode) -> io::Result<()> {
+    if !self.timers.iter().any(|(other, _)| other == name) {
+      self.timers.push((name.to_string(), Timer::new()?));
+    }
+    match self.get_mut(name) {
+      Some(timer) => timer.start(after, mode),
+      None => Ok(())
+    }
+  }
+
+  /// Detiene el temporizador `name`, si existe
+  pub fn stop(&mut self, name: &str) -> io::Result<()> {
+    match self.get_mut(name) {
+      Some(timer) => timer.stop(),
+      None => Ok(())
+    }
+  }
+
+  /// Elimina el temporizador `name`, si existe
+  pub fn remove(&mut self, name: &str) {
+    self.timers.retain(|(other, _)| other != name);
+  }
+
+  /// Temporizador `name`
+  pub fn get_mut(&mut self, name: &str) -> Option<&mut Timer> {
+    self.timers.iter_mut().find(|(other, _)| other == name).map(|(_, timer)| timer)
+  }
+
+  /// Devuelve los temporizadores que vencieron desde la última consulta.
+  /// No se bloquea.
+  pub fn poll(&mut self) -> io::Result<Vec<TimerEvent>> {
+    let mut events = Vec::new();
+    for (name, timer) in &self.timers {
+      let expirations = timer.expirations()?;
+      if expirations > 0 {
+        events.push(TimerEvent {name: name.clone(), expirations});
+      }
+    }
+    Ok(events)
+  }
+
+  /// Espera a que venza algún temporizador, hasta `timeout` (`None` espera
+  /// indefinidamente), y devuelve los vencidos. Si una señal interrumpe la
+  /// espera devuelve el error `Interrupted`.
+  pub fn wait(&mut self, timeout: Option<Duration>) -> io::Result<Vec<TimerEvent>> {
+    let mut poll_fds: Vec<libc::pollfd> = self.timers.iter()
+      .map(|(_, timer)| libc::pollfd {fd: timer.fd, events: libc::POLLIN, revents: 0})
+      .collect();
+    let timeout_ms = match timeout {
+      Some(timeout) => timeout.as_millis().min(i32::MAX as u128) as i32,
+      None => -1
+    };
+    poll_all(&mut poll_fds, timeout_ms)?;
+    self.poll()
+  }
+}
+
+/// Llama a `poll` sobre `poll_fds`
+fn poll_all(poll_fds: &mut [libc::pollfd], timeout_ms: i32) -> io::Result<c_int> {
+  let result;
+  unsafe {
+    result = libc::poll(poll_fds.as_mut_ptr(), poll_fds.len() as libc::nfds_t, timeout_ms);
+  }
+  if result >= 0 {
+    Ok(result)
+  } else {
+    Err(Error::last_os_error())
+  }
+}
+
+/// Convierte una duración a `timespec`
+fn timespec(duration: Duration) -> libc::timespec {
+  libc::timespec {
+    tv_sec: duration.as_secs() as libc::time_t,
+    tv_nsec: libc::c_long::from(duration.subsec_nanos())
+  }
+}
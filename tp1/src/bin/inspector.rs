@@ -0,0 +1,28 @@
+extern crate concurrentes;
+extern crate rand;
+extern crate libc;
+extern crate tp1;
+
+use concurrentes::signal::SignalHandlerDispatcher;
+
+use tp1::handlers::signal_handler::QuitHandler;
+use tp1::live_objects::{live_object, inspector::Inspector};
+
+use std::env;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+/// Lanza un inspector, o un agente de prefectura si recibe `--navy`
+fn main() -> io::Result<()> {
+  let is_inspector = !env::args().any(|arg| arg == "--navy");
+  let quit_handler = Arc::new(Mutex::new(QuitHandler::new()));
+  let _sigint = SignalHandlerDispatcher::register(libc::SIGINT, quit_handler.clone())?;
+  let _sigterm = SignalHandlerDispatcher::register(libc::SIGTERM, quit_handler.clone())?;
+
+  let runner = live_object::LiveObjectRunner::new(quit_handler)?;
+
+  let current_port = runner.get_random_port();
+  let inspector = Inspector::new(current_port, is_inspector)?;
+  runner.run(inspector)?;
+  runner.exit()
+}
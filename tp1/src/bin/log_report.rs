@@ -0,0 +1,39 @@
+extern crate concurrentes;
+extern crate tp1;
+
+use concurrentes::log::{DEFAULT_LOG_PATH, LOG_PATH_VAR};
+
+use tp1::log_analysis::LogAnalysis;
+
+use std::env;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Lee los logs pasados por parámetro (del más viejo al más nuevo) o, si no
+/// se pasa ninguno, el log del lago junto a sus archivos rotados, e imprime
+/// la línea de tiempo de cada entidad y los totales de la corrida.
+fn main() -> io::Result<()> {
+  let mut paths: Vec<String> = env::args().skip(1).collect();
+  if paths.is_empty() {
+    paths = rotated_logs(&env::var(LOG_PATH_VAR).unwrap_or_else(|_| DEFAULT_LOG_PATH.to_string()));
+  }
+  let mut analysis = LogAnalysis::new();
+  for path in &paths {
+    analysis.read(BufReader::new(File::open(path)?))?;
+  }
+  print!("{}", analysis);
+  Ok(())
+}
+
+/// `path` precedido por sus archivos rotados existentes, del más viejo al más nuevo
+fn rotated_logs(path: &str) -> Vec<String> {
+  let mut paths: Vec<String> = (1..)
+    .map(|n| format!("{}.{}", path, n))
+    .take_while(|rotated| Path::new(rotated).exists())
+    .collect();
+  paths.reverse();
+  paths.push(path.to_string());
+  paths
+}
@@ -1,5 +1,6 @@
-use concurrentes::signal::SignalHandler;
-use libc;
+use concurrentes::signal::{SignalHandler, SignalInfo};
+
+use std::collections::VecDeque;
 
 /// Handler para una salida agraciada
 #[derive(Default)]
@@ -7,18 +8,16 @@ pub struct QuitHandler {
   quit: bool
 }
 
-/// Handler genérico multiuso
+/// Handler que encola cada señal recibida, para atenderlas de a una.
+/// Pensado para señales de tiempo real enviadas con `signal::queue`.
 #[derive(Default)]
-pub struct GenericHandler {
-  handled: bool
+pub struct QueueHandler {
+  received: VecDeque<SignalInfo>
 }
 
 impl SignalHandler for QuitHandler {
   fn handle(&mut self) {
     self.quit = true;
-    unsafe {
-      libc::close(0);
-    }
   }
 }
 
@@ -34,25 +33,22 @@ impl QuitHandler {
   }
 }
 
-impl SignalHandler for GenericHandler {
-  fn handle(&mut self) {
-    self.handled = true;
-  }
-}
+impl SignalHandler for QueueHandler {
+  fn handle(&mut self) {}
 
-impl GenericHandler {
-  /// Constructor default 
-  pub fn new() -> GenericHandler {
-    GenericHandler {handled: false}
+  fn handle_info(&mut self, info: &SignalInfo) {
+    self.received.push_back(*info);
   }
+}
 
-  /// Devuelve `true` si la señal fue capturada
-  pub fn get_handled(&self) -> bool {
-    self.handled
+impl QueueHandler {
+  /// Constructor default
+  pub fn new() -> QueueHandler {
+    QueueHandler {received: VecDeque::new()}
   }
 
-  /// Resetea el indicador de captura
-  pub fn reset(&mut self) {
-    self.handled = false;
+  /// Quita y devuelve las señales recibidas, en orden de llegada
+  pub fn drain(&mut self) -> Vec<SignalInfo> {
+    self.received.drain(..).collect()
   }
-}
\ No newline at end of file
+}
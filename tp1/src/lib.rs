@@ -5,6 +5,7 @@
 /// Interpreta argumentos pasados por linea de comando para facilitar el
 /// lanzamiento de procesos.
 extern crate concurrentes;
+extern crate chrono;
 extern crate getopts;
 extern crate libc;
 extern crate ncurses;
@@ -16,3 +17,5 @@ pub mod live_objects;
 pub mod handlers;
 /// Clases utilitarias
 pub mod misc;
+/// Análisis del log de una corrida
+pub mod log_analysis;
@@ -1,26 +1,58 @@
-use libc;
+use libc::c_int;
 use rand;
 use rand::Rng;
 
-use concurrentes::signal::signal;
-use concurrentes::log::{GLOBAL_LOG, LogSeverity};
+use concurrentes::signal::{SignalHandlerDispatcher, SignalMask, SignalRegistration};
+use concurrentes::signal::{queue, rt_signal, wait_for};
+use concurrentes::log::{GLOBAL_LOG, Log, LogSeverity};
+
+use handlers::signal_handler::QueueHandler;
 
 use live_objects::lake::Lake;
 use live_objects::live_object::LiveObject;
 
 use std::cell::RefCell;
 use std::io;
-use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use std::thread::sleep;
 
+/// Señal de tiempo real (`SIGRTMIN + n`) con la que se piden inspecciones a
+/// los barcos, y con la que éstos las confirman
+const INSPECTION_RT_SIGNAL: c_int = 0;
+/// Tiempo máximo que se espera la confirmación de una inspección. Supera a
+/// los 10 segundos que el barco puede pasar esperando un pasajero, durante
+/// los cuales difiere las inspecciones.
+const ACK_TIMEOUT_SECS: u64 = 15;
+/// Valor adjunto al pedir la inspección de los pasajeros
+pub const PASSENGER_INSPECTION: i32 = -1;
+/// Valor adjunto al pedir la revisión del barco por prefectura
+pub const SHIP_INSPECTION: i32 = -2;
+
+// Comienzo de los mensajes con los que `log_analysis` reconoce los eventos
+// del inspector en los logs de formato texto
+/// Creación del inspector
+pub const STARTED_MESSAGE: &str = "Iniciando inspector";
+/// Pedido de inspección a un barco
+pub const INSPECTION_MESSAGE: &str = "Iniciando inspección del barco";
+
+/// Señal con la que se piden y confirman inspecciones
+pub fn inspection_signal() -> c_int {
+  rt_signal(INSPECTION_RT_SIGNAL).expect("No hay señales de tiempo real disponibles")
+}
+
 pub struct Inspector {
   current_port: i32,
   status: Status,
   /// Puede ser inspector (true) o prefectura (false)
   is_inspector: bool,
   /// Ultimo barco inspeccionado
-  last_inspection: u32
+  last_inspection: u32,
+  /// Manejador de las confirmaciones que envían los barcos al terminar cada
+  /// inspección
+  ack_handler: Arc<Mutex<QueueHandler>>,
+  /// Registro del manejador de confirmaciones
+  _registration: SignalRegistration
 }
 
 #[derive(Debug)]
@@ -31,6 +63,11 @@ enum Status {
 
 impl LiveObject for Inspector {
   fn tick(&mut self, lake: &RefCell<Lake>) -> Result<(), io::Error> {
+    let acks = self.ack_handler.lock().unwrap().drain();
+    for ack in acks {
+      let msg = format!("El barco {} confirmó tarde la inspección en el puerto {}", ack.pid, ack.value);
+      log!(msg.as_str(), &LogSeverity::INFO);
+    }
     match self.status {
       Status::Inspect => self.inspect(lake),
       Status::Travel => self.travel(lake)
@@ -41,11 +78,15 @@ impl LiveObject for Inspector {
 
 
 impl Inspector {
-  pub fn new(current_port: i32, is_inspector: bool) -> Inspector {
-    log!(format!("Iniciando inspector en el puerto {}", current_port).as_str(), &LogSeverity::INFO);
+  pub fn new(current_port: i32, is_inspector: bool) -> io::Result<Inspector> {
+    Log::set_entity(if is_inspector { "inspector" } else { "navy" });
+    log!(format!("{} en el puerto {}", STARTED_MESSAGE, current_port).as_str(), &LogSeverity::INFO);
     let status = Status::Inspect;
     let last_inspection = 0;
-    Inspector { current_port, status, is_inspector, last_inspection }
+    // El barco confirma devolviendo la misma señal que recibió
+    let ack_handler = Arc::new(Mutex::new(QueueHandler::new()));
+    let _registration = SignalHandlerDispatcher::register(inspection_signal(), ack_handler.clone())?;
+    Ok(Inspector { current_port, status, is_inspector, last_inspection, ack_handler, _registration })
   }
 
   pub fn travel(&mut self, lake: &RefCell<Lake>) {
@@ -60,12 +101,25 @@ impl Inspector {
   pub fn inspect(&mut self, lake: &RefCell<Lake>) {
     if let Some(ship) = lake.borrow_mut().get_ship_at(0) {
       if self.last_inspection != ship {
-        log!(format!("Iniciando inspección del barco {}", ship).as_str(), &LogSeverity::INFO);
+        // Bloqueo la confirmación antes de pedir la inspección, para no perderla
+        let _mask = match SignalMask::block(&[inspection_signal()]) {
+          Ok(mask) => mask,
+          Err(e) => {
+            let msg = format!("No se pudo bloquear la confirmación, se omite la inspección: {}", e);
+            log!(msg.as_str(), &LogSeverity::WARN);
+            self.status = Status::Travel;
+            return;
+          }
+        };
+        log!(INFO, "inspection", "{} {}", INSPECTION_MESSAGE, ship; ship = ship);
         self.last_inspection = ship;
-        if self.is_inspector {
-          signal(ship as i32, libc::SIGUSR1);
-        } else {
-          signal(ship as i32, libc::SIGUSR2);
+        let kind = if self.is_inspector { PASSENGER_INSPECTION } else { SHIP_INSPECTION };
+        match queue(ship as i32, inspection_signal(), kind) {
+          Ok(()) => self.wait_ack(ship),
+          Err(e) => {
+            let msg = format!("No se pudo pedir la inspección al barco {}: {}", ship, e);
+            log!(msg.as_str(), &LogSeverity::WARN);
+          }
         }
       }
     } else {
@@ -73,4 +127,24 @@ impl Inspector {
     }
     self.status = Status::Travel;
   }
+
+  /// Espera, sin consumir CPU, a que el barco confirme la inspección
+  fn wait_ack(&self, ship: u32) {
+    let timeout = Duration::from_secs(ACK_TIMEOUT_SECS);
+    match wait_for(&[inspection_signal()], Some(timeout)) {
+      Ok(Some(ack)) => {
+        log!(INFO, "inspected", "El barco {} terminó la inspección en el puerto {}", ack.pid, ack.value;
+          ship = ack.pid, port = ack.value);
+      },
+      Ok(None) => {
+        let msg = format!("El barco {} no confirmó la inspección en {} segundos",
+          ship, ACK_TIMEOUT_SECS);
+        log!(msg.as_str(), &LogSeverity::WARN);
+      },
+      Err(e) => {
+        let msg = format!("Se interrumpió la espera de la confirmación del barco {}: {}", ship, e);
+        log!(msg.as_str(), &LogSeverity::WARN);
+      }
+    }
+  }
 }
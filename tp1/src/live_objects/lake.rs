@@ -3,19 +3,23 @@ use misc::config::Config;
 use rand;
 use rand::Rng;
 
-use concurrentes::ipc;
+use concurrentes::ipc::IpcOptions;
 use concurrentes::ipc::flock::FileLock;
 use concurrentes::ipc::named_pipe;
-use concurrentes::ipc::Key;
+use concurrentes::ipc::{Key, KeyAllocator};
 use concurrentes::ipc::shmem::Shmem;
 use concurrentes::log::{GLOBAL_LOG, LogSeverity};
+use concurrentes::patterns::Turnstile;
 use std::io;
-use std::fs::remove_file;
 use std::process;
 
 const NUM_PORTS_PARAM: &str = "lake ports";
 const STATUS_FILE: &str = "status.lock";
 const REPORT_FILE: &str = "report.lock";
+const STATUS_KEY: &str = "status";
+const REPORT_KEY: &str = "report";
+/// Directorio con los archivos de clave de los IPCs del lago
+pub const KEYS_DIR: &str = "lake.keys";
 
 /// Contenedor de los IPCs fijos del lago
 ///
@@ -30,7 +34,7 @@ const REPORT_FILE: &str = "report.lock";
 /// Estos pipes sirven para que los **Pasajeros** puedan comunicarle a cada 
 /// **Barco** que van a subir a viajar.
 ///
-/// * *boarding_locks*: Nombres de los semáforos con los que se limita a uno la
+/// * *boarding_locks*: Nombres de los molinetes con los que se limita a uno la
 /// cantidad de pasajeros accediendo al puerto. De esta forma se evita que dos
 /// pasajeros o más escriban en el FIFO en simultaneo.
 ///
@@ -44,6 +48,9 @@ const REPORT_FILE: &str = "report.lock";
 ///
 /// * *report_mem*: Memoria compartida con dos contadores:
 ///  pasajeros multados y barcos decomisados
+///
+/// Las claves de las memorias compartidas se obtienen de un `KeyAllocator`
+/// ubicado en `KEYS_DIR`.
 pub struct Lake {
   lake_ports: Vec<FileLock>,
   boarding_locks: Vec<String>,
@@ -52,7 +59,8 @@ pub struct Lake {
   status_lock: FileLock,
   report_lock: FileLock,
   status_mem: Shmem<u32>,
-  report_mem: Shmem<u32>
+  report_mem: Shmem<u32>,
+  keys: KeyAllocator
 }
 
 impl Lake {
@@ -65,11 +73,14 @@ impl Lake {
     let num_ports = num_ports_str.parse::<u32>().expect("Lake ports invalid");
     let status_lock = FileLock::create(STATUS_FILE.to_string()).unwrap();
     let report_lock = FileLock::create(REPORT_FILE.to_string()).unwrap();
+    let mut keys = KeyAllocator::new(KEYS_DIR).unwrap();
     // intento crearla, si fallo intento abrir la existente
-    let mut status_mem = get_status_mem(num_ports).unwrap();
+    let status_key = keys.allocate(STATUS_KEY).unwrap();
+    let mut status_mem = get_status_mem(&status_key, num_ports).unwrap();
     status_mem.attach(0).unwrap();
     // idem arriba
-    let mut report_mem = get_report_mem().unwrap();
+    let report_key = keys.allocate(REPORT_KEY).unwrap();
+    let mut report_mem = get_report_mem(&report_key).unwrap();
     report_mem.attach(0).unwrap();
     let mut lake_ports = Vec::new();
     let mut boarding_pipes = Vec::new();
@@ -90,19 +101,23 @@ impl Lake {
       lake_ports.push(port_lock);
     }
     Lake {lake_ports, boarding_pipes, boarding_locks, confirmation_pipes,
-      status_lock, status_mem, report_lock, report_mem}
+      status_lock, status_mem, report_lock, report_mem, keys}
   }
 
   /// Crea los IPCs en caso de que no existan
   pub fn create_ipcs(&mut self) -> io::Result<()> {
+    let mut options = IpcOptions::new();
+    options.create(true).exclusive(true).mode(0o660);
     for lock in &self.boarding_locks {
       FileLock::create(lock.to_string())?;
+      Turnstile::get(lock.as_str(), &options)?.init()?;
     }
+    options.mode(0o644);
     for pipe in &self.boarding_pipes {
-      named_pipe::NamedPipe::create(pipe.as_str(), 0o0644)?;
+      named_pipe::NamedPipe::create(pipe.as_str(), &options)?;
     }
     for pipe in &self.confirmation_pipes {
-      named_pipe::NamedPipe::create(pipe.as_str(), 0o0644)?;
+      named_pipe::NamedPipe::create(pipe.as_str(), &options)?;
     }
     // Inicializo memoria compartida
     let report_mem_cell = self.report_mem.get_array_mut();
@@ -125,7 +140,8 @@ impl Lake {
       port.destroy()?;
     }
     for lock in &self.boarding_locks {
-      remove_file(lock)?;
+      Turnstile::get(lock.as_str(), &IpcOptions::new())?.remove();
+      FileLock::remove(lock.as_str())?;
     }
     for pipe in &self.boarding_pipes {
       named_pipe::NamedPipe::unlink(pipe.as_str())?;
@@ -134,15 +150,27 @@ impl Lake {
       named_pipe::NamedPipe::unlink(pipe.as_str())?;
     }
     self.status_lock.destroy()?;
-    Ok(())
+    self.report_mem.destroy()?;
+    self.report_lock.destroy()?;
+    self.keys.release(STATUS_KEY)?;
+    self.keys.release(REPORT_KEY)?;
+    self.keys.destroy()
+  }
+
+  /// Obtiene la clave ya asignada a `purpose` (por ejemplo, la del semáforo
+  /// de un pasajero)
+  pub fn get_key(&mut self, purpose: &str) -> io::Result<Key> {
+    self.keys.lookup(purpose)
   }
 
   /// Abre y devuelve un FIFO correspondiente al puerto, y especializado
-  /// para lectura. Estos FIFOs son usados para que el barco levante pasajeros
+  /// para lectura. Estos FIFOs son usados para que el barco levante pasajeros.
+  /// Se abre sin bloquearse, por lo que el barco debe esperar a que un
+  /// pasajero escriba antes de leer.
   pub fn get_board_pipe_reader(&mut self, current_port: i32)
     -> io::Result<named_pipe::NamedPipeReader> {
     let board_pipe_path = &self.boarding_pipes[current_port as usize];
-    named_pipe::NamedPipeReader::open(board_pipe_path.as_str())
+    named_pipe::NamedPipeReader::open_nonblocking(board_pipe_path.as_str())
   }
 
   /// Abre y devuelve un FIFO correspondiente al puerto, y especializado
@@ -204,9 +232,11 @@ impl Lake {
     self.lake_ports[port as usize].unlock()
   }
 
-  pub fn get_boarding_lock(&self, port: i32) -> io::Result<FileLock>{
-    let boarding_lock_path = self.boarding_locks[port as usize].clone();
-    FileLock::create(boarding_lock_path)
+  /// Obtiene el molinete por el que pasan, de a uno, los pasajeros que
+  /// abordan en el puerto
+  pub fn get_boarding_turnstile(&self, port: i32) -> io::Result<Turnstile>{
+    let boarding_lock_path = &self.boarding_locks[port as usize];
+    Turnstile::get(boarding_lock_path.as_str(), &IpcOptions::new())
   }
 
   pub fn get_ship_at(&mut self, port: i32) -> Option<u32> {
@@ -238,29 +268,27 @@ impl Lake {
 /// Rust exige que tenga la memoria compartida creada. Como puede haberlo
 /// creado mi proceso u otro proceso, intento crearlo, y si fallo, intento
 /// abrir la memoria existente
-fn get_status_mem(num_ports: u32) -> io::Result<Shmem<u32>> {
-  let shmem_key = Key::ftok(STATUS_FILE, 0)?;
-  let flags = ipc::IPC_CREAT | ipc::IPC_EXCL | 0o660;
-  match Shmem::<u32>::get(&shmem_key, num_ports as usize, flags) {
+fn get_status_mem(shmem_key: &Key, num_ports: u32) -> io::Result<Shmem<u32>> {
+  let mut options = IpcOptions::new();
+  options.create(true).exclusive(true);
+  match Shmem::<u32>::get(shmem_key, num_ports as usize, &options) {
     Ok(shmem) => Ok(shmem),
     // Si falla al crear, intento abrir el que existe
     Err(_) => {
-      let flags = 0o660;
-      Shmem::<u32>::get(&shmem_key, num_ports as usize, flags)
+      Shmem::<u32>::get(shmem_key, num_ports as usize, &IpcOptions::new())
     }
   }
 }
 
 /// Idem status mem
-fn get_report_mem() -> io::Result<Shmem<u32>> {
-  let shmem_key = Key::ftok(REPORT_FILE, 0)?;
-  let flags = ipc::IPC_CREAT | ipc::IPC_EXCL | 0o660;
-  match Shmem::<u32>::get(&shmem_key, 2 as usize, flags) {
+fn get_report_mem(shmem_key: &Key) -> io::Result<Shmem<u32>> {
+  let mut options = IpcOptions::new();
+  options.create(true).exclusive(true);
+  match Shmem::<u32>::get(shmem_key, 2, &options) {
     Ok(shmem) => Ok(shmem),
     // Si falla al crear, intento abrir el que existe
     Err(_) => {
-      let flags = 0o660;
-      Shmem::<u32>::get(&shmem_key, 2 as usize, flags)
+      Shmem::<u32>::get(shmem_key, 2, &IpcOptions::new())
     }
   }
 }
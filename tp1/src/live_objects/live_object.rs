@@ -1,3 +1,5 @@
+use concurrentes::signal::SignalHandlerDispatcher;
+
 use handlers::signal_handler::QuitHandler;
 
 use live_objects::main_lock::MainLock;
@@ -6,7 +8,7 @@ use misc::config::Config;
 
 use std::cell::RefCell;
 use std::io;
-use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 
 const MAIN_LOCK_FILENAME : &str = "tp1.lock";
 const MAIN_CONFIG_FILENAME: &str = "config.cfg";
@@ -27,13 +29,13 @@ pub trait LiveObject {
 /// de configuración, de forma que no se pueda cambiar la configuración si
 /// hay instancias del lago corriendo.
 pub struct LiveObjectRunner {
-  quit_handler: Rc<RefCell<QuitHandler>>,
+  quit_handler: Arc<Mutex<QuitHandler>>,
   lake: RefCell<Lake>
 }
 
 impl LiveObjectRunner {
   /// 
-  pub fn new(quit_handler: Rc<RefCell<QuitHandler>>) -> io::Result<(LiveObjectRunner)> {
+  pub fn new(quit_handler: Arc<Mutex<QuitHandler>>) -> io::Result<LiveObjectRunner> {
     
     // Lock principal
     let mut main_lock = MainLock::new(MAIN_LOCK_FILENAME)?;
@@ -49,7 +51,7 @@ impl LiveObjectRunner {
     }
     // Marco que hay un proceso más usando los IPCs
     lock_info.counter_inc();
-    lock_info.save(MAIN_LOCK_FILENAME)?;
+    lock_info.save(&main_lock.lock.file)?;
     main_lock.lock.unlock()?;
     Ok(LiveObjectRunner{quit_handler, lake: RefCell::new(lake)})
   }
@@ -57,8 +59,10 @@ impl LiveObjectRunner {
   /// Main loop
   pub fn run<T: LiveObject>(&self, mut object: T) -> io::Result<()> {
     // Start object
-    while !self.quit_handler.borrow().has_graceful_quit() {
+    SignalHandlerDispatcher::dispatch_pending();
+    while !self.quit_handler.lock().unwrap().has_graceful_quit() {
       object.tick(&self.lake)?;
+      SignalHandlerDispatcher::dispatch_pending();
     }
     Ok(())
   }
@@ -73,7 +77,7 @@ impl LiveObjectRunner {
     let mut lock_info = main_lock.get_info();
     lock_info.counter_dec();
     // Guardo que estoy cerrando el proceso
-    lock_info.save(MAIN_LOCK_FILENAME)?;
+    lock_info.save(&main_lock.lock.file)?;
     // Si soy el último, elimino IPCs
     if lock_info.is_counter_zero() {
       self.lake.borrow_mut().destroy()?;
@@ -1,8 +1,9 @@
 use concurrentes::ipc::flock::FileLock;
+use concurrentes::signal::EintrPolicy;
 
 use std::fs::{File, metadata};
 use std::io;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
 use std::io::{Error, ErrorKind};
 use std::str::FromStr;
 use std::time::SystemTime;
@@ -19,12 +20,16 @@ pub struct MainLockInfo {
 
 impl MainLock {
   pub fn new(path : &'static str) -> Result<MainLock, Error> {
-    let lock = FileLock::create(String::from_str(path).unwrap())?;
+    let mut lock = FileLock::create(String::from_str(path).unwrap())?;
+    // El contador de procesos se actualiza aunque llegue una señal de salida
+    lock.set_eintr_policy(EintrPolicy::Retry);
     Ok(MainLock{path, lock})
   } 
 
+  /// Lee la información a través del archivo lockeado. Abrir y cerrar otro
+  /// descriptor del mismo archivo liberaría el lock de `fcntl`.
   pub fn get_info(&self) -> MainLockInfo {
-  match MainLockInfo::read_info(self.path) {
+  match MainLockInfo::read_info(&self.lock.file) {
       Ok(info) => info,
       Err(_e) => {
         MainLockInfo::create(self.path).unwrap()
@@ -34,8 +39,8 @@ impl MainLock {
 }
 
 impl MainLockInfo {
-  pub fn read_info(path: &str) -> Result<MainLockInfo, Error> {
-    let file = File::open(path)?;
+  pub fn read_info(mut file: &File) -> Result<MainLockInfo, Error> {
+    file.seek(SeekFrom::Start(0))?;
     let mut buf = BufReader::new(file);
     // Read process counter
     let mut buf_line = String::new();
@@ -76,8 +81,9 @@ impl MainLockInfo {
     self.process_counter -= 1;
   }
 
-  pub fn save(&self, path: &str) -> io::Result<()>{
-    let mut file = File::create(path)?;
+  pub fn save(&self, mut file: &File) -> io::Result<()>{
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
     let data = format!("{:?}\n{:?}\n", self.process_counter, self.timestamp);
     file.write_all(data.as_bytes())
   }
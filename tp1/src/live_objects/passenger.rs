@@ -1,14 +1,12 @@
 use rand;
 use rand::Rng;
 
-use concurrentes::ipc;
-use concurrentes::ipc::Key;
+use concurrentes::ipc::{IpcOptions, KeyAllocator};
 use concurrentes::ipc::named_pipe;
-use concurrentes::ipc::flock::FileLock;
 use concurrentes::ipc::semaphore::Semaphore;
-use concurrentes::log::{GLOBAL_LOG, LogSeverity};
+use concurrentes::log::{GLOBAL_LOG, Log, LogSeverity};
 
-use live_objects::lake::Lake;
+use live_objects::lake::{Lake, KEYS_DIR};
 use live_objects::live_object::LiveObject;
 
 use std::cell::RefCell;
@@ -19,6 +17,12 @@ use std::process;
 use std::time::Duration;
 use std::thread::sleep;
 
+// Comienzo de los mensajes con los que `log_analysis` reconoce los eventos
+// del pasajero en los logs de formato texto
+/// Creación del pasajero
+pub const STARTED_MESSAGE: &str = "Pasajero";
+/// Multa por boleto vencido
+pub const FINED_MESSAGE: &str = "Mi boleto está vencido";
 
 /// Entidad pasajero
 ///
@@ -33,6 +37,7 @@ pub struct Passenger {
   id: u32,
   status: Status,
   sem: Semaphore,
+  keys: KeyAllocator,
   inspection: bool,
   navy: bool
 }
@@ -65,19 +70,36 @@ impl LiveObject for Passenger {
 }
 
 impl Passenger {
-  pub fn new(current_port: i32, destination: i32) -> Passenger {
+  pub fn new(current_port: i32, destination: i32) -> io::Result<Passenger> {
     let id = process::id();
-    let flags = ipc::IPC_CREAT | ipc::IPC_EXCL | 0o660;
+    let mut options = IpcOptions::new();
+    options.create(true).exclusive(true).mode(0o660);
+    let purpose = Passenger::key_purpose(id);
+    let mut keys = KeyAllocator::new(KEYS_DIR)?;
+    let key = keys.allocate(&purpose)?;
+    let mut sem = match Semaphore::get(&key, &options) {
+      Ok(sem) => sem,
+      Err(e) => {
+        let _ = keys.release(&purpose);
+        return Err(e);
+      }
+    };
     let pipe_path = format!("passenger-{:?}.fifo", id);
-    let lock_pipe_path = format!("passenger-{:?}.fifo.lock", id);
-    named_pipe::NamedPipe::create(pipe_path.as_str(), flags).unwrap();
-    FileLock::create(lock_pipe_path.clone()).unwrap();
-    let key = Key::ftok(&lock_pipe_path, 0).unwrap();
-    let sem = Semaphore::get(&key, flags).unwrap();
+    if let Err(e) = named_pipe::NamedPipe::create(pipe_path.as_str(), &options) {
+      sem.remove();
+      let _ = keys.release(&purpose);
+      return Err(e);
+    }
     let status = Status::WaitShip;
-    let msg = format!("Pasajero {}: desde el puerto {} a {}", id, current_port, destination);
+    Log::set_entity("passenger");
+    let msg = format!("{} {}: desde el puerto {} a {}", STARTED_MESSAGE, id, current_port, destination);
         log!(msg.as_str(), &LogSeverity::INFO);
-    Passenger {current_port, destination, id, status, sem, inspection: false, navy: false}
+    Ok(Passenger {current_port, destination, id, status, sem, keys, inspection: false, navy: false})
+  }
+
+  /// Propósito con el que se asigna la clave del semáforo del pasajero `id`
+  pub fn key_purpose(id: u32) -> String {
+    format!("passenger-{:?}", id)
   }
 
   fn wait_for_destination(&mut self) -> io::Result<()>{
@@ -132,7 +154,8 @@ impl Passenger {
       let ticket = rng.gen::<u32>() % 10;
       // Si está vencido
       if ticket != 0 {
-        log!("Mi boleto está vencido", &LogSeverity::DEBUG);
+        log!(INFO, "fined", "{}, desciendo en el puerto {}", FINED_MESSAGE, self.current_port;
+          port = self.current_port);
         writeln!(writer, "{}", self.id)?;
         self.destination = self.current_port;
         lake.borrow_mut().report_passenger();
@@ -164,17 +187,16 @@ impl Passenger {
     let msg = format!("Tomando el barco en el puerto {}, destino {}",
       self.current_port, self.destination);
     log!(msg.as_str(), &LogSeverity::INFO);
-    // En cierta forma el lock es un molinete :D
-    let mut lock = lake.borrow_mut().get_boarding_lock(self.current_port)?;
-    lock.lock_exclusive()?;
+    // Los pasajeros pasan de a uno por el molinete del puerto
+    let turnstile = lake.borrow().get_boarding_turnstile(self.current_port)?;
+    turnstile.lock()?;
     log!("Obteniendo fifo", &LogSeverity::DEBUG);
     let mut writer = lake.borrow_mut().
       get_board_pipe_writer(self.current_port)?;
     log!("Obtenido fifo", &LogSeverity::DEBUG);
     writeln!(writer, "{}", self.id)?;
-    let writer_msg = format!("Datos enviados: {}", self.id.to_string());
-    log!(writer_msg.as_str(), &LogSeverity::DEBUG);
-    lock.unlock()?;
+    log!(format!("Datos enviados: {}", self.id).as_str(), &LogSeverity::DEBUG);
+    turnstile.unlock()?;
     self.status = Status::WaitDestination;
     Ok(())
   }
@@ -213,9 +235,13 @@ impl Passenger {
 impl Drop for Passenger {
   fn drop(&mut self) {
     let pipe_path = format!("passenger-{:?}.fifo", self.id);
-    let lock_pipe_path = format!("passenger-{:?}.fifo.lock", self.id);
     self.sem.remove();
-    named_pipe::NamedPipe::unlink(pipe_path.as_str()).unwrap();
-    named_pipe::NamedPipe::unlink(lock_pipe_path.as_str()).unwrap();
+    // Los recursos pueden haber sido eliminados por otro (concu-ipcs clean)
+    if let Err(e) = named_pipe::NamedPipe::unlink(pipe_path.as_str()) {
+      log!(format!("No se pudo eliminar el FIFO {}: {}", pipe_path, e).as_str(), &LogSeverity::WARN);
+    }
+    if let Err(e) = self.keys.release(&Passenger::key_purpose(self.id)) {
+      log!(format!("No se pudo liberar la clave del pasajero: {}", e).as_str(), &LogSeverity::WARN);
+    }
   }
 }
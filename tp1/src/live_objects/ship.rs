@@ -1,42 +1,59 @@
 use rand;
 use rand::Rng;
 
-use libc;
-
-use concurrentes::ipc::Key;
-use concurrentes::ipc::flock::FileLock;
+use concurrentes::ipc::IpcOptions;
 use concurrentes::ipc::semaphore::Semaphore;
 use concurrentes::ipc::named_pipe;
-use concurrentes::log::{GLOBAL_LOG, LogSeverity};
-use concurrentes::signal::{SignalHandlerDispatcher, alarm};
+use concurrentes::log::{GLOBAL_LOG, Log, LogSeverity};
+use concurrentes::signal::{SignalHandlerDispatcher, SignalInfo, SignalMask, SignalRegistration};
+use concurrentes::signal::queue;
+use concurrentes::timer::{Timer, TimerMode};
 
-use handlers::signal_handler::GenericHandler;
+use handlers::signal_handler::QueueHandler;
 
+use live_objects::inspector::{inspection_signal, PASSENGER_INSPECTION, SHIP_INSPECTION};
 use live_objects::lake::Lake;
 use live_objects::live_object::LiveObject;
+use live_objects::passenger::Passenger;
 
 use std::cell::RefCell;
 use std::io;
 use std::io::{Error, BufRead, BufReader, Write};
-use std::rc::Rc;
+use std::os::unix::io::AsRawFd;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use std::thread::sleep;
 
+/// Tiempo máximo que el barco espera a que aborde un pasajero
+const BOARDING_TIMEOUT_MS: u64 = 10000;
+
+// Comienzo de los mensajes con los que `log_analysis` reconoce los eventos
+// del barco en los logs de formato texto
+/// Estado inicial del barco
+pub const INITIAL_STATE_MESSAGE: &str = "Comienza en el estado";
+/// Cambio de estado del barco
+pub const STATE_MESSAGE: &str = "Pasa al estado";
+/// Abordaje de un pasajero
+pub const BOARDED_MESSAGE: &str = "Abordó el pasajero";
+/// Descenso de un pasajero
+pub const LEFT_MESSAGE: &str = "Descargando pasajero";
+
 /// Barco de pasajeros
 /// Posee los siguientes atributos
 /// * Puerto de destino
 /// * Vector de ids de los pasajeros a bordo
-/// * Manejador de la señal sigalrm para no bloquearse eternamente al
-/// levantar un pasajero
+/// * Temporizador para no bloquearse eternamente al levantar un pasajero
+/// * Cola de pedidos de inspección
 /// * Estado del barco
 pub struct Ship {
   /// Una cantidad máxima de pasajeros que puede levantar
   current_capacity: u32,
   destination: i32,
   passenger_vec: Vec<u32>,
-  sigalarm_handler: Rc<RefCell<GenericHandler>>,
-  sigusr1_handler: Rc<RefCell<GenericHandler>>,
-  sigusr2_handler: Rc<RefCell<GenericHandler>>,
+  boarding_timer: Timer,
+  inspection_handler: Arc<Mutex<QueueHandler>>,
+  /// Registro de los manejadores, que se quitan al destruir el barco
+  _registrations: Vec<SignalRegistration>,
   status: Status
 }
 
@@ -55,23 +72,18 @@ enum Status {
 
 impl LiveObject for Ship {
   fn tick(&mut self, lake: &RefCell<Lake>) -> Result<(), Error> {
-    if self.sigusr1_handler.borrow().get_handled() {
-      log!("Comienza una inspección", &LogSeverity::INFO);
-      self.inspect_passengers(lake)?;
-      self.sigusr1_handler.borrow_mut().reset();
-    }
-    if self.sigusr2_handler.borrow().get_handled() {
-      self.inspect_ship(lake)?;
-      self.sigusr2_handler.borrow_mut().reset();
+    let requests = self.inspection_handler.lock().unwrap().drain();
+    for request in requests {
+      self.attend_inspection(lake, request)?;
     }
     match self.status {
       Status::Travel => self.travel(lake)?,
       Status::LeavePassengers => self.leave_passenger(lake)?,
       Status::PickPassengers => {
         if self.current_capacity > 0 {
-          self.pick_passenger(lake);
+          self.pick_passenger(lake)?;
         } else {
-          self.status = Status::Disembark;
+          self.set_status(Status::Disembark);
         }
       },
       Status::Disembark => self.disembark(lake)?
@@ -82,17 +94,27 @@ impl LiveObject for Ship {
 
 
 impl Ship {
-  pub fn new(current_capacity: u32, destination: i32) -> Ship {
-    // Acá me recontra abuso del supuesto de que hay un sólo barco por proceso
-    let sigalarm_handler = Rc::new(RefCell::new(GenericHandler::new()));
-    let sigusr1_handler = Rc::new(RefCell::new(GenericHandler::new()));
-    let sigusr2_handler = Rc::new(RefCell::new(GenericHandler::new()));
-    SignalHandlerDispatcher::register(libc::SIGALRM, sigalarm_handler.clone());
-    SignalHandlerDispatcher::register(libc::SIGUSR1, sigusr1_handler.clone());
-    SignalHandlerDispatcher::register(libc::SIGUSR2, sigusr2_handler.clone());
-    Ship {current_capacity, destination,
-      sigalarm_handler, sigusr1_handler, sigusr2_handler,
-      status: Status::Travel, passenger_vec: Vec::new()}
+  pub fn new(current_capacity: u32, destination: i32) -> io::Result<Ship> {
+    // Cada barco encadena sus propios manejadores a los que ya existan
+    let inspection_handler = Arc::new(Mutex::new(QueueHandler::new()));
+    let _registrations = vec![
+      SignalHandlerDispatcher::register(inspection_signal(), inspection_handler.clone())?
+    ];
+    let boarding_timer = Timer::new()?;
+    Log::set_entity("ship");
+    log!(INFO, "state", "{} {:?} hacia el puerto {}", INITIAL_STATE_MESSAGE, Status::Travel, destination;
+      state = format!("{:?}", Status::Travel), port = destination);
+    Ok(Ship {current_capacity, destination,
+      boarding_timer, inspection_handler, _registrations,
+      status: Status::Travel, passenger_vec: Vec::new()})
+  }
+
+  /// Cambia el estado del barco y lo registra en el log, junto al puerto en
+  /// el que está (o hacia el que viaja)
+  fn set_status(&mut self, status: Status) {
+    log!(INFO, "state", "{} {:?} en el puerto {}", STATE_MESSAGE, status, self.destination;
+      state = format!("{:?}", status), port = self.destination);
+    self.status = status;
   }
 
   fn travel(&mut self, lake: &RefCell<Lake>) -> io::Result<()> {
@@ -105,7 +127,7 @@ impl Ship {
     sleep(travel_time);
     lake.borrow_mut().lock_port(self.destination)?;
     log!("Puerto bloqueado", &LogSeverity::DEBUG);
-    self.status = Status::LeavePassengers;
+    self.set_status(Status::LeavePassengers);
     Ok(())
   }
 
@@ -120,45 +142,64 @@ impl Ship {
     lake.borrow_mut().unlock_port(self.destination)?;
     log!("Puerto desbloqueado", &LogSeverity::DEBUG);
     self.destination = lake.borrow_mut().get_next_port(self.destination);
-    self.status = Status::Travel;
+    self.set_status(Status::Travel);
     Ok(())
   }
 
   /// Levanta los pasajeros esperando en un puerto
   /// Abre un FIFO en forma de lectura en el cuál los pasajeros escriben,
-  /// de a uno, su PID. Utiliza una alarma al abrir el FIFO, de forma de que
-  /// si nadie abrió la otra punta del FIFO, pasados N segundos, se desbloquee
-  /// lanzando SIGALRM
-  /// Si se lanza SIGALRM el manejador de esta señal se activa y el próximo
-  /// estado pasa a ser Disembark
-  fn pick_passenger(&mut self, lake: &RefCell<Lake>) -> Option<u32> {
+  /// de a uno, su PID. Espera a que un pasajero escriba con un temporizador,
+  /// de forma de que si nadie abordó pasados BOARDING_TIMEOUT_MS
+  /// milisegundos, el próximo estado pase a ser Disembark
+  fn pick_passenger(&mut self, lake: &RefCell<Lake>) -> io::Result<()> {
+    // Las inspecciones que lleguen mientras espero al pasajero se atienden
+    // en el próximo tick, sin interrumpir la espera
+    let _mask = SignalMask::block(&[inspection_signal()])?;
     log!("Obteniendo fifo", &LogSeverity::DEBUG);
-    alarm(10);
     let pipe_reader = lake.borrow_mut().get_board_pipe_reader(self.destination);
-    alarm(0);
+    let pipe_reader = pipe_reader.and_then(|reader| self.wait_passenger(reader));
     match pipe_reader {
-      Ok(reader) => {
-        let parsed_data = self.parse_passenger(reader);
-        if let Some(passenger) =  parsed_data {
+      Ok(Some(reader)) => {
+        if let Some(passenger) = self.parse_passenger(reader) {
           self.passenger_vec.push(passenger);
         }
-        parsed_data
+      }
+      Ok(None) => {
+        let msg = format!("Nadie abordó en el puerto {} durante {} msecs",
+          self.destination, BOARDING_TIMEOUT_MS);
+        log!(msg.as_str(), &LogSeverity::INFO);
+        self.set_status(Status::Disembark);
+      }
+      // Una señal (por ejemplo la de terminar) interrumpió la espera
+      Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {
+        log!("Se interrumpió la espera de pasajeros", &LogSeverity::DEBUG);
       }
       Err(e) => {
         let msg = format!("Error al esperar pasajero en el puerto {}: {:?}",
           self.destination, e);
         log!(msg.as_str(), &LogSeverity::ERROR);
-        None
       }
-    };
-    if self.sigalarm_handler.borrow().get_handled() {
-      self.status = Status::Disembark;
-      self.sigalarm_handler.borrow_mut().reset();
     }
-    let msg = format!("Hay lugar para {:?} pasajeros",
-      self.current_capacity);
-    log!(msg.as_str(), &LogSeverity::DEBUG);
-    None
+    log!(format!("Hay lugar para {:?} pasajeros", self.current_capacity).as_str(),
+      &LogSeverity::DEBUG);
+    Ok(())
+  }
+
+  /// Espera a que un pasajero escriba en el FIFO de abordaje, o a que venza
+  /// el temporizador. Devuelve el FIFO listo para leer en forma bloqueante,
+  /// o `None` si nadie abordó.
+  fn wait_passenger(&mut self, reader: named_pipe::NamedPipeReader)
+    -> io::Result<Option<named_pipe::NamedPipeReader>> {
+    let timeout = Duration::from_millis(BOARDING_TIMEOUT_MS);
+    self.boarding_timer.start(timeout, TimerMode::OneShot)?;
+    let ready = self.boarding_timer.wait_readable(reader.as_raw_fd());
+    self.boarding_timer.stop()?;
+    if ready? {
+      reader.set_nonblocking(false)?;
+      Ok(Some(reader))
+    } else {
+      Ok(None)
+    }
   }
 
   fn leave_passenger(&mut self, lake: &RefCell<Lake>) -> io::Result<()>{
@@ -167,11 +208,40 @@ impl Ship {
   }
 
   fn inspect_passengers(&mut self, lake: &RefCell<Lake>) -> io::Result<()> {
-    self.notify_passengers(lake, -1)
+    self.notify_passengers(lake, PASSENGER_INSPECTION)
   }
 
   fn inspect_ship(&mut self, lake: &RefCell<Lake>) -> io::Result<()> {
-    self.notify_passengers(lake, -2)
+    self.notify_passengers(lake, SHIP_INSPECTION)
+  }
+
+  /// Atiende un pedido de inspección y se lo confirma al inspector que lo
+  /// envió, adjuntando el puerto en el que se encuentra el barco
+  fn attend_inspection(&mut self, lake: &RefCell<Lake>, request: SignalInfo) -> io::Result<()> {
+    match request.value {
+      PASSENGER_INSPECTION => {
+        let msg = format!("Comienza una inspección pedida por el proceso {} (uid {})",
+          request.pid, request.uid);
+        log!(msg.as_str(), &LogSeverity::INFO);
+        self.inspect_passengers(lake)?;
+      },
+      SHIP_INSPECTION => {
+        let msg = format!("Comienza una revisión de prefectura pedida por el proceso {} (uid {})",
+          request.pid, request.uid);
+        log!(msg.as_str(), &LogSeverity::INFO);
+        self.inspect_ship(lake)?;
+      },
+      other => {
+        let msg = format!("Pedido de inspección desconocido {} del proceso {}", other, request.pid);
+        log!(msg.as_str(), &LogSeverity::WARN);
+        return Ok(());
+      }
+    }
+    if let Err(e) = queue(request.pid, request.signal, self.destination) {
+      let msg = format!("No se pudo confirmar la inspección al proceso {}: {}", request.pid, e);
+      log!(msg.as_str(), &LogSeverity::WARN);
+    }
+    Ok(())
   }
 
   /// Notifica a todos los pasajeros que llegó a un puerto
@@ -179,15 +249,15 @@ impl Ship {
   /// -1 para notificar una inspección,
   /// -2 para forzar descenso
   fn notify_passengers(&mut self, lake: &RefCell<Lake>, port: i32) -> io::Result<()>{
+    // Difiero las inspecciones hasta terminar el intercambio con los pasajeros
+    let _mask = SignalMask::block(&[inspection_signal()])?;
     let mut left_passengers = Vec::new();
     for passenger in &self.passenger_vec {
       log!(format!("Notificando pasajero {}", passenger).as_str(), &LogSeverity::DEBUG);
       let pipe_path = format!("passenger-{:?}.fifo", passenger);
-      let lock_pipe_path = format!("passenger-{:?}.fifo.lock", passenger);
-      FileLock::create(lock_pipe_path.clone()).unwrap();
-      let key = Key::ftok(&lock_pipe_path, 0).unwrap();
+      let key = lake.borrow_mut().get_key(&Passenger::key_purpose(*passenger))?;
       log!(format!("Obteniendo semaforo {}", passenger).as_str(), &LogSeverity::DEBUG);
-      let sem = Semaphore::get(&key, 0).unwrap();
+      let sem = Semaphore::get(&key, &IpcOptions::new()).unwrap();
       // Habilita a un pasajero a que responda
       sem.signal()?;
       log!(format!("Abriendo FIFO {} para escribir puerto", pipe_path).as_str(), &LogSeverity::DEBUG);
@@ -197,7 +267,8 @@ impl Ship {
       log!(format!("Enviado puerto {}", self.destination).as_str(), &LogSeverity::DEBUG);
       // Si el pasajero responde con su pid, lo descargo
       if let Some(reply) = self.read_passenger_reply(lake)? {
-        log!(format!("Descargando pasajero {:?}", reply).as_str(), &LogSeverity::INFO);
+        log!(INFO, "left", "{} {:?}", LEFT_MESSAGE, reply;
+          passenger = reply, port = self.destination);
         left_passengers.push(reply);
       }
       log!(format!("Terminé de notificar pasajero {}", passenger).as_str(), &LogSeverity::DEBUG);
@@ -210,7 +281,7 @@ impl Ship {
         .map(|e| self.passenger_vec.remove(e));
       self.current_capacity += 1;
     }
-    self.status = Status::PickPassengers;
+    self.set_status(Status::PickPassengers);
     Ok(())
   }
 
@@ -242,9 +313,8 @@ impl Ship {
       Ok(_) => {
         buf_line.pop();
         let passenger_id = buf_line.parse::<u32>().unwrap();
-        let msg = format!("Abordó el pasajero {:?}",
-          passenger_id);
-        log!(msg.as_str(), &LogSeverity::INFO);
+        log!(INFO, "boarded", "{} {:?}", BOARDED_MESSAGE, passenger_id;
+          passenger = passenger_id, port = self.destination);
         self.current_capacity -= 1;
         Some(passenger_id)
       },
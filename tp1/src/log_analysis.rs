@@ -0,0 +1,542 @@
+use chrono::{DateTime, NaiveDateTime};
+
+use live_objects::{inspector, passenger, ship};
+
+use std::char;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io;
+use std::io::BufRead;
+
+/// Formato de la fecha en las líneas de texto del log. No tiene
+/// milisegundos, por lo que los tiempos de esos logs se informan en segundos.
+const TEXT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Línea del log, leída en formato texto o JSON
+///
+/// En formato texto no hay tipo de entidad, evento ni campos: se deducen
+/// del comienzo del mensaje, con las constantes que usan las entidades del
+/// lago al escribirlo (por ejemplo `ship::STATE_MESSAGE`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogLine {
+  pub timestamp: NaiveDateTime,
+  pub pid: u32,
+  pub severity: String,
+  pub entity: Option<String>,
+  pub event: String,
+  pub message: String,
+  pub fields: HashMap<String, String>
+}
+
+impl LogLine {
+  /// Interpreta una línea de log, en cualquiera de los dos formatos.
+  /// Devuelve `None` si la línea no tiene un formato válido.
+  pub fn parse(line: &str) -> Option<LogLine> {
+    if line.trim_start().starts_with('{') {
+      LogLine::parse_json(line)
+    } else {
+      LogLine::parse_text(line)
+    }
+  }
+
+  /// `<fecha> [<pid>] [<severidad>] - mensaje`
+  fn parse_text(line: &str) -> Option<LogLine> {
+    let date = line.get(..19)?;
+    let timestamp = NaiveDateTime::parse_from_str(date, TEXT_DATE_FORMAT).ok()?;
+    let rest = line.get(19..)?.trim_start();
+    let (pid, rest) = bracketed(rest)?;
+    let (severity, rest) = bracketed(rest.trim_start())?;
+    let message = rest.trim_start().trim_start_matches("- ").to_string();
+    let (entity, event, fields) = classify(&message);
+    Some(LogLine {timestamp, pid: pid.parse().ok()?, severity: severity.to_string(),
+      entity: entity.map(|entity| entity.to_string()), event: event.to_string(), message, fields})
+  }
+
+  /// Un objeto JSON con `timestamp`, `pid`, `severity`, `entity`, `event`,
+  /// `message` y los campos del registro
+  fn parse_json(line: &str) -> Option<LogLine> {
+    let mut fields = parse_flat_json(line)?;
+    let timestamp = DateTime::parse_from_rfc3339(&fields.remove("timestamp")??).ok()?.naive_local();
+    let pid = fields.remove("pid")??.parse().ok()?;
+    let severity = fields.remove("severity")??;
+    let entity = fields.remove("entity")?;
+    let event = fields.remove("event")??;
+    let message = fields.remove("message")??;
+    let fields = fields.into_iter()
+      .filter_map(|(key, value)| value.map(|value| (key, value)))
+      .collect();
+    Some(LogLine {timestamp, pid, severity, entity, event, message, fields})
+  }
+
+  /// Campo `key` interpretado como número
+  pub fn number(&self, key: &str) -> Option<i64> {
+    self.fields.get(key).and_then(|value| value.parse().ok())
+  }
+}
+
+/// Separa `[contenido] resto`
+fn bracketed(text: &str) -> Option<(&str, &str)> {
+  if !text.starts_with('[') {
+    return None;
+  }
+  let end = text.find(']')?;
+  Some((&text[1..end], &text[end + 1..]))
+}
+
+/// Deduce la entidad, el evento y los campos de un mensaje en formato
+/// texto, a partir de los mensajes que escriben las entidades del lago
+fn classify(message: &str) -> (Option<&'static str>, &'static str, HashMap<String, String>) {
+  let mut fields = HashMap::new();
+  let last = message.split_whitespace().last().unwrap_or_default().to_string();
+  let state_change = message.strip_prefix(ship::STATE_MESSAGE)
+    .or_else(|| message.strip_prefix(ship::INITIAL_STATE_MESSAGE));
+  if let Some(rest) = state_change {
+    // "<mensaje> X en el puerto N" / "<mensaje> X hacia el puerto N"
+    if let Some(state) = rest.split_whitespace().next() {
+      fields.insert("state".to_string(), state.to_string());
+    }
+    fields.insert("port".to_string(), last);
+    (Some("ship"), "state", fields)
+  } else if message.starts_with(ship::BOARDED_MESSAGE) {
+    fields.insert("passenger".to_string(), last);
+    (Some("ship"), "boarded", fields)
+  } else if message.starts_with(ship::LEFT_MESSAGE) {
+    fields.insert("passenger".to_string(), last);
+    (Some("ship"), "left", fields)
+  } else if message.starts_with(passenger::FINED_MESSAGE) {
+    fields.insert("port".to_string(), last);
+    (Some("passenger"), "fined", fields)
+  } else if message.starts_with(passenger::STARTED_MESSAGE) {
+    (Some("passenger"), "log", fields)
+  } else if message.starts_with(inspector::INSPECTION_MESSAGE) {
+    fields.insert("ship".to_string(), last);
+    (Some("inspector"), "inspection", fields)
+  } else if message.starts_with(inspector::STARTED_MESSAGE) {
+    (Some("inspector"), "log", fields)
+  } else {
+    (None, "log", fields)
+  }
+}
+
+/// Interpreta un objeto JSON plano, cuyos valores son strings, números o
+/// `null`, como el que escribe `concurrentes::log` en formato JSON. Los
+/// números se devuelven como texto y `null` como `None`.
+fn parse_flat_json(line: &str) -> Option<HashMap<String, Option<String>>> {
+  let mut chars = line.trim().chars().peekable();
+  let mut object = HashMap::new();
+  if chars.next()? != '{' {
+    return None;
+  }
+  loop {
+    skip_spaces(&mut chars);
+    match chars.next()? {
+      '}' => return Some(object),
+      ',' => continue,
+      '"' => {
+        let key = parse_json_string(&mut chars)?;
+        skip_spaces(&mut chars);
+        if chars.next()? != ':' {
+          return None;
+        }
+        skip_spaces(&mut chars);
+        let value = if chars.peek() == Some(&'"') {
+          chars.next();
+          Some(parse_json_string(&mut chars)?)
+        } else {
+          let mut literal = String::new();
+          while let Some(&c) = chars.peek() {
+            if c == ',' || c == '}' || c.is_whitespace() {
+              break;
+            }
+            literal.push(c);
+            chars.next();
+          }
+          if literal == "null" { None } else { Some(literal) }
+        };
+        object.insert(key, value);
+      },
+      _ => return None
+    }
+  }
+}
+
+fn skip_spaces<I: Iterator<Item = char>>(chars: &mut ::std::iter::Peekable<I>) {
+  while chars.peek().is_some_and(|c| c.is_whitespace()) {
+    chars.next();
+  }
+}
+
+/// Lee un string JSON, ya consumida la comilla inicial
+fn parse_json_string<I: Iterator<Item = char>>(chars: &mut I) -> Option<String> {
+  let mut value = String::new();
+  loop {
+    match chars.next()? {
+      '"' => return Some(value),
+      '\\' => match chars.next()? {
+        'n' => value.push('\n'),
+        'r' => value.push('\r'),
+        't' => value.push('\t'),
+        'b' => value.push('\u{8}'),
+        'f' => value.push('\u{c}'),
+        'u' => {
+          let mut code = parse_json_hex(chars)?;
+          // Los caracteres fuera del plano básico vienen como par sustituto
+          if (0xD800..0xDC00).contains(&code) {
+            if chars.next()? != '\\' || chars.next()? != 'u' {
+              return None;
+            }
+            let low = parse_json_hex(chars)?;
+            if !(0xDC00..0xE000).contains(&low) {
+              return None;
+            }
+            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+          }
+          value.push(char::from_u32(code)?);
+        },
+        other => value.push(other)
+      },
+      c => value.push(c)
+    }
+  }
+}
+
+/// Lee los 4 dígitos hexadecimales de un escape `\u`
+fn parse_json_hex<I: Iterator<Item = char>>(chars: &mut I) -> Option<u32> {
+  let code: String = chars.by_ref().take(4).collect();
+  if code.len() != 4 {
+    return None;
+  }
+  u32::from_str_radix(&code, 16).ok()
+}
+
+/// Cambio de estado de un barco
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transition {
+  pub timestamp: NaiveDateTime,
+  pub state: String,
+  pub port: Option<i64>
+}
+
+/// Actividad de una entidad (un proceso) durante la corrida
+#[derive(Clone, Debug, Default)]
+pub struct EntityTimeline {
+  /// Tipo de entidad, si se pudo determinar
+  pub entity: Option<String>,
+  /// Cambios de estado, en orden
+  pub transitions: Vec<Transition>,
+  /// Eventos destacados (abordajes, descensos, multas, inspecciones), en orden
+  pub events: Vec<LogLine>,
+  pub boardings: u32,
+  pub fines: u32,
+  /// Tiempo que pasó en cada puerto, desde que llegó (`LeavePassengers`)
+  /// hasta que volvió a viajar (`Travel`)
+  pub dock_times: Vec<chrono::Duration>
+}
+
+impl EntityTimeline {
+  /// Agrega una línea del proceso
+  fn add(&mut self, line: LogLine) {
+    // En modo fork el hijo loguea como el lanzador hasta crear su entidad
+    if line.entity.is_some() {
+      self.entity = line.entity.clone();
+    }
+    match line.event.as_str() {
+      "state" => {
+        let state = line.fields.get("state").cloned().unwrap_or_default();
+        if state == "Travel" {
+          // La estadía actual son las transiciones desde el último viaje
+          let stay = self.transitions.iter()
+            .rposition(|transition| transition.state == "Travel")
+            .map_or(0, |travel| travel + 1);
+          let arrival = self.transitions[stay..].iter()
+            .find(|transition| transition.state == "LeavePassengers");
+          if let Some(arrival) = arrival {
+            self.dock_times.push(line.timestamp.signed_duration_since(arrival.timestamp));
+          }
+        }
+        let port = line.number("port");
+        self.transitions.push(Transition {timestamp: line.timestamp, state, port});
+      },
+      "log" => {},
+      event => {
+        match event {
+          "boarded" => self.boardings += 1,
+          "fined" => self.fines += 1,
+          _ => {}
+        }
+        self.events.push(line);
+      }
+    }
+  }
+}
+
+/// Análisis de un log: actividad de cada proceso, agrupada por pid
+#[derive(Default)]
+pub struct LogAnalysis {
+  pub timelines: BTreeMap<u32, EntityTimeline>,
+  /// Líneas que no se pudieron interpretar
+  pub invalid_lines: usize,
+  /// Líneas en formato texto, cuyas fechas no tienen milisegundos
+  pub text_lines: usize
+}
+
+impl LogAnalysis {
+  /// Crea un análisis vacío
+  pub fn new() -> LogAnalysis {
+    LogAnalysis {timelines: BTreeMap::new(), invalid_lines: 0, text_lines: 0}
+  }
+
+  /// Lee todas las líneas de `reader`. Los logs rotados deben leerse del
+  /// más viejo al más nuevo.
+  pub fn read<R: BufRead>(&mut self, reader: R) -> io::Result<()> {
+    for line in reader.lines() {
+      let line = line?;
+      if line.trim().is_empty() {
+        continue;
+      }
+      match LogLine::parse(&line) {
+        Some(parsed) => {
+          if !line.trim_start().starts_with('{') {
+            self.text_lines += 1;
+          }
+          self.timelines.entry(parsed.pid).or_default().add(parsed)
+        },
+        None => self.invalid_lines += 1
+      }
+    }
+    Ok(())
+  }
+
+  /// Cantidad de procesos por tipo de entidad
+  pub fn entity_counts(&self) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for timeline in self.timelines.values() {
+      let entity = timeline.entity.clone().unwrap_or_else(|| "sin identificar".to_string());
+      *counts.entry(entity).or_insert(0) += 1;
+    }
+    counts
+  }
+
+  /// Total de abordajes
+  pub fn boardings(&self) -> u32 {
+    self.timelines.values().map(|timeline| timeline.boardings).sum()
+  }
+
+  /// Total de multas
+  pub fn fines(&self) -> u32 {
+    self.timelines.values().map(|timeline| timeline.fines).sum()
+  }
+
+  /// Tiempo promedio en puerto de todos los barcos y cantidad de atraques,
+  /// o `None` si ningún barco completó un atraque
+  pub fn average_dock_time(&self) -> Option<(chrono::Duration, usize)> {
+    let docks: Vec<&chrono::Duration> = self.timelines.values()
+      .flat_map(|timeline| timeline.dock_times.iter())
+      .collect();
+    if docks.is_empty() {
+      return None;
+    }
+    let total_ms: i64 = docks.iter().map(|dock| dock.num_milliseconds()).sum();
+    Some((chrono::Duration::milliseconds(total_ms / docks.len() as i64), docks.len()))
+  }
+}
+
+impl fmt::Display for LogAnalysis {
+  /// Línea de tiempo de cada entidad, seguida de los totales. Si hay líneas
+  /// en formato texto, los tiempos se muestran en segundos enteros.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let precise = self.text_lines == 0;
+    let time_format = if precise { "%H:%M:%S%.3f" } else { "%H:%M:%S" };
+    for (pid, timeline) in &self.timelines {
+      if timeline.transitions.is_empty() && timeline.events.is_empty() {
+        continue;
+      }
+      let entity = timeline.entity.clone().unwrap_or_else(|| "sin identificar".to_string());
+      writeln!(f, "{} {}", entity, pid)?;
+      // Transiciones y eventos intercalados por fecha
+      let mut entries: Vec<(NaiveDateTime, String)> = timeline.transitions.iter()
+        .map(|transition| (transition.timestamp, match transition.port {
+          Some(port) => format!("{} (puerto {})", transition.state, port),
+          None => transition.state.clone()
+        }))
+        .chain(timeline.events.iter().map(|event| (event.timestamp,
+          format!("[{}] {}", event.event, event.message))))
+        .collect();
+      entries.sort_by_key(|entry| entry.0);
+      for (timestamp, description) in entries {
+        writeln!(f, "  {}  {}", timestamp.format(time_format), description)?;
+      }
+      if !timeline.dock_times.is_empty() {
+        let total_ms: i64 = timeline.dock_times.iter().map(|dock| dock.num_milliseconds()).sum();
+        let average_ms = total_ms / timeline.dock_times.len() as i64;
+        writeln!(f, "  Abordajes: {}, tiempo promedio en puerto: {}", timeline.boardings,
+          format_seconds(average_ms, precise))?;
+      }
+    }
+    writeln!(f, "Totales")?;
+    for (entity, count) in self.entity_counts() {
+      writeln!(f, "  Procesos {}: {}", entity, count)?;
+    }
+    writeln!(f, "  Abordajes: {}", self.boardings())?;
+    writeln!(f, "  Multas: {}", self.fines())?;
+    match self.average_dock_time() {
+      Some((average, docks)) => writeln!(f, "  Tiempo promedio en puerto: {} ({} atraques)",
+        format_seconds(average.num_milliseconds(), precise), docks)?,
+      None => writeln!(f, "  Tiempo promedio en puerto: sin atraques completos")?
+    }
+    if self.invalid_lines > 0 {
+      writeln!(f, "  Líneas no reconocidas: {}", self.invalid_lines)?;
+    }
+    Ok(())
+  }
+}
+
+/// Duración en segundos, con décimas sólo si las fechas tienen milisegundos
+fn format_seconds(millis: i64, precise: bool) -> String {
+  if precise {
+    format!("{:.1} s", millis as f64 / 1000.0)
+  } else {
+    format!("{} s", (millis as f64 / 1000.0).round())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::Cursor;
+
+  /// Línea en formato texto del proceso `pid`, a los `secs` segundos
+  fn text_line(secs: u32, pid: u32, message: &str) -> String {
+    format!("2026-10-19 07:45:{:02} [{}] [INFO] - {}", secs, pid, message)
+  }
+
+  fn parse_text(secs: u32, pid: u32, message: &str) -> LogLine {
+    LogLine::parse(&text_line(secs, pid, message)).unwrap()
+  }
+
+  #[test]
+  fn parses_text_state_changes() {
+    let line = parse_text(13, 7674, "Pasa al estado LeavePassengers en el puerto 3");
+    assert_eq!(line.pid, 7674);
+    assert_eq!(line.severity, "INFO");
+    assert_eq!(line.entity, Some("ship".to_string()));
+    assert_eq!(line.event, "state");
+    assert_eq!(line.fields["state"], "LeavePassengers");
+    assert_eq!(line.number("port"), Some(3));
+
+    let line = parse_text(13, 7674, "Comienza en el estado Travel hacia el puerto 2");
+    assert_eq!(line.event, "state");
+    assert_eq!(line.fields["state"], "Travel");
+    assert_eq!(line.number("port"), Some(2));
+  }
+
+  #[test]
+  fn parses_text_entity_events() {
+    let line = parse_text(14, 7674, "Abordó el pasajero 7680");
+    assert_eq!((line.entity.as_deref(), line.event.as_str()), (Some("ship"), "boarded"));
+    assert_eq!(line.number("passenger"), Some(7680));
+
+    let line = parse_text(15, 7674, "Descargando pasajero 7680");
+    assert_eq!((line.entity.as_deref(), line.event.as_str()), (Some("ship"), "left"));
+
+    let line = parse_text(16, 7680, "Mi boleto está vencido, desciendo en el puerto 1");
+    assert_eq!((line.entity.as_deref(), line.event.as_str()), (Some("passenger"), "fined"));
+    assert_eq!(line.number("port"), Some(1));
+
+    let line = parse_text(17, 7690, "Iniciando inspección del barco 7674");
+    assert_eq!((line.entity.as_deref(), line.event.as_str()), (Some("inspector"), "inspection"));
+    assert_eq!(line.number("ship"), Some(7674));
+
+    let line = parse_text(18, 7690, "Paseando 1000 msecs");
+    assert_eq!((line.entity, line.event.as_str()), (None, "log"));
+  }
+
+  #[test]
+  fn parses_json_lines() {
+    let line = LogLine::parse(concat!(
+      r#"{"timestamp":"2026-10-19T07:45:36.884+00:00","pid":8165,"severity":"INFO","#,
+      r#""entity":"ship","event":"state","message":"Pasa al estado LeavePassengers en el puerto 2","#,
+      r#""state":"LeavePassengers","port":2}"#)).unwrap();
+    assert_eq!(line.pid, 8165);
+    assert_eq!(line.timestamp.format("%H:%M:%S%.3f").to_string(), "07:45:36.884");
+    assert_eq!(line.entity, Some("ship".to_string()));
+    assert_eq!(line.event, "state");
+    assert_eq!(line.fields["state"], "LeavePassengers");
+    assert_eq!(line.number("port"), Some(2));
+
+    let line = LogLine::parse(concat!(
+      r#"{"timestamp":"2026-10-19T07:45:33.953+00:00","pid":8157,"severity":"INFO","#,
+      r#""entity":null,"event":"log","message":"Iniciando lago"}"#)).unwrap();
+    assert_eq!(line.entity, None);
+    assert!(line.fields.is_empty());
+  }
+
+  #[test]
+  fn parses_json_escapes() {
+    let line = LogLine::parse(concat!(
+      r#"{"timestamp":"2026-10-19T07:45:33.953+00:00","pid":1,"severity":"WARN","#,
+      r#""entity":"launcher","event":"log","message":"\"a\"\\\n\t\u00e9\ud83d\ude00"}"#)).unwrap();
+    assert_eq!(line.message, "\"a\"\\\n\té\u{1F600}");
+  }
+
+  #[test]
+  fn rejects_invalid_lines() {
+    assert_eq!(LogLine::parse(""), None);
+    assert_eq!(LogLine::parse("no es un log"), None);
+    assert_eq!(LogLine::parse("2026-10-19 07:45:13 [abc] [INFO] - x"), None);
+    assert_eq!(LogLine::parse(r#"{"timestamp":"2026-10-19T07:45:33.953+00:00","pid":1"#), None);
+    assert_eq!(LogLine::parse(r#"{"pid":1,"message":"\ud83d"}"#), None);
+  }
+
+  #[test]
+  fn measures_dock_time_from_arrival_to_departure() {
+    let mut timeline = EntityTimeline::default();
+    timeline.add(parse_text(0, 1, "Comienza en el estado Travel hacia el puerto 0"));
+    timeline.add(parse_text(10, 1, "Pasa al estado LeavePassengers en el puerto 0"));
+    timeline.add(parse_text(10, 1, "Pasa al estado PickPassengers en el puerto 0"));
+    timeline.add(parse_text(12, 1, "Abordó el pasajero 2"));
+    timeline.add(parse_text(14, 1, "Pasa al estado Disembark en el puerto 0"));
+    timeline.add(parse_text(15, 1, "Pasa al estado Travel en el puerto 1"));
+    assert_eq!(timeline.entity, Some("ship".to_string()));
+    assert_eq!(timeline.transitions.len(), 5);
+    assert_eq!(timeline.boardings, 1);
+    assert_eq!(timeline.dock_times, vec![chrono::Duration::seconds(5)]);
+  }
+
+  #[test]
+  fn ignores_stays_without_arrival() {
+    let mut timeline = EntityTimeline::default();
+    timeline.add(parse_text(0, 1, "Pasa al estado Travel en el puerto 0"));
+    timeline.add(parse_text(1, 1, "Pasa al estado LeavePassengers en el puerto 0"));
+    timeline.add(parse_text(4, 1, "Pasa al estado Travel en el puerto 1"));
+    // Estadía sin LeavePassengers: no cuenta el tiempo desde el puerto anterior
+    timeline.add(parse_text(10, 1, "Pasa al estado PickPassengers en el puerto 1"));
+    timeline.add(parse_text(12, 1, "Pasa al estado Travel en el puerto 2"));
+    assert_eq!(timeline.dock_times, vec![chrono::Duration::seconds(3)]);
+  }
+
+  #[test]
+  fn totals_by_entity() {
+    let log = [
+      text_line(0, 1, "Comienza en el estado Travel hacia el puerto 0"),
+      text_line(1, 2, "Pasajero 2: desde el puerto 0 a 1"),
+      text_line(2, 1, "Pasa al estado LeavePassengers en el puerto 0"),
+      text_line(3, 1, "Abordó el pasajero 2"),
+      text_line(6, 1, "Pasa al estado Travel en el puerto 1"),
+      text_line(7, 2, "Mi boleto está vencido, desciendo en el puerto 1"),
+      text_line(8, 3, "Iniciando lago"),
+      "línea cortada".to_string()
+    ].join("\n");
+    let mut analysis = LogAnalysis::new();
+    analysis.read(Cursor::new(log)).unwrap();
+    let counts = analysis.entity_counts();
+    assert_eq!(counts["ship"], 1);
+    assert_eq!(counts["passenger"], 1);
+    assert_eq!(counts["sin identificar"], 1);
+    assert_eq!(analysis.boardings(), 1);
+    assert_eq!(analysis.fines(), 1);
+    assert_eq!(analysis.average_dock_time(), Some((chrono::Duration::seconds(4), 1)));
+    assert_eq!(analysis.invalid_lines, 1);
+    assert_eq!(analysis.text_lines, 7);
+  }
+}
@@ -9,9 +9,13 @@ mod handlers;
 mod live_objects;
 mod misc;
 
+use concurrentes::ipc::pid_file::PidFile;
+use concurrentes::ipc::registry;
 use concurrentes::process;
-use concurrentes::log::{GLOBAL_LOG, LogSeverity};
+use concurrentes::process::{ProcessGroup, SpawnOptions};
+use concurrentes::log::{GLOBAL_LOG, Log, LogSeverity};
 use concurrentes::signal::SignalHandlerDispatcher;
+use concurrentes::supervisor::{ExitAction, RestartPolicy, Supervisor};
 
 use handlers::signal_handler::QuitHandler;
 
@@ -20,24 +24,42 @@ use live_objects::live_object;
 use misc::launcher::Launcher;
 use misc::tui::{Tui, PromptSelection};
 use misc::args_parser::ArgsParser;
+use misc::usage_report::UsageReport;
 
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::env;
 use std::io;
 use std::process::id as pid;
-use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Máximo de reinicios de cada barco de la flota dentro de la ventana
+const FLEET_MAX_RESTARTS: usize = 5;
+/// Ventana de tiempo en la que se cuentan los reinicios de la flota
+const FLEET_RESTART_WINDOW_SECS: u64 = 60;
+/// Tiempo que tienen las entidades para terminar al cerrar la aplicación
+const QUIT_TIMEOUT_SECS: u64 = 3;
+/// Intervalo con el que se consulta si las entidades terminaron
+const QUIT_POLL_MILLIS: u64 = 100;
+/// Archivo de pid que impide correr dos lanzadores sobre el mismo lago
+const PID_FILENAME: &str = "tp1.pid";
 
 
 fn main() -> io::Result<()> {
   let args: Vec<String> = env::args().collect();
   let handler = ArgsParser::new();
-  let quit_handler = Rc::new(RefCell::new(QuitHandler::new()));
-  SignalHandlerDispatcher::register(libc::SIGINT, quit_handler.clone());
-  SignalHandlerDispatcher::register(libc::SIGTERM, quit_handler.clone());
+  let quit_handler = Arc::new(Mutex::new(QuitHandler::new()));
+  let _sigint = SignalHandlerDispatcher::register(libc::SIGINT, quit_handler.clone())?;
+  let _sigterm = SignalHandlerDispatcher::register(libc::SIGTERM, quit_handler.clone())?;
   // Si las opciones son válidas, inicio el programa. Si hubo pedido de
   // ayuda o error de sintaxis, no hago nada.
   if let Some(options) = handler.handle(args) {
+    // En modo daemon el lanzador pasa a segundo plano antes de tomar el lago
+    if options.get("daemon") == Some(&1) {
+      process::daemonize()?;
+    }
     run(quit_handler, options)?;
   } 
   
@@ -45,63 +67,183 @@ fn main() -> io::Result<()> {
 }
 
 
-fn run(quit_handler: Rc<RefCell<QuitHandler>>,
+fn run(quit_handler: Arc<Mutex<QuitHandler>>,
     options: HashMap<String, i32>) -> io::Result<()> {
   let mut selection_vector = options_as_vector(&options);
+  let exec_mode = options.get("exec") == Some(&1);
+  let fleet = *options.get("fleet").unwrap();
+  let daemon = options.get("daemon") == Some(&1);
+  // Los IPCs de todas las entidades se atribuyen al lanzador, que es quien
+  // vive durante toda la corrida
+  let launcher_pid = pid() as libc::pid_t;
+  env::set_var(registry::OWNER_ENV, launcher_pid.to_string());
+  // Un único lanzador por lago
+  let _pid_file = PidFile::acquire(PID_FILENAME)?;
+  Log::set_entity("launcher");
   let options_cell = RefCell::new(options);
-  // Inicio la interfaz de texto
-  let tui = Tui::new(options_cell);
+  // Inicio la interfaz de texto, salvo que corra como daemon
+  let tui = if daemon { Tui::headless(options_cell) } else { Tui::new(options_cell) };
   let mut quit = false;
-  let mut child_result = None;
   let mut child_counter = 0;
+  // Las entidades corren en su propio grupo, para poder terminarlas juntas
+  let mut group = ProcessGroup::new();
+  // Consumo de recursos de los hijos, por tipo de entidad
+  let mut report = UsageReport::new();
+  // Recolecta a los hijos que terminan mientras se muestra el menú
+  let mut reaper = process::ChildReaper::register()?;
   // Objeto que se encarga de crear y destruir IPCs
   // También provee a los hijos de acceso a los IPCs creados por el padre.
   let mut runner = live_object::LiveObjectRunner::new(quit_handler.clone())?;
-  while !quit {
-    // Levanto las opciones pasadas por argumento
-    let mut selection = selection_vector.pop();
-    if selection.is_none() {
-      // Si ya levanté todas, le permito al usuario
-      selection = tui.prompt();
+  // Flota de barcos que se relanzan si fallan
+  let mut supervisor = Supervisor::new(FLEET_MAX_RESTARTS,
+    Duration::from_secs(FLEET_RESTART_WINDOW_SECS));
+  // Cualquier error lanzando o uniendo entidades corta el ciclo, pero no la
+  // limpieza de los IPCs del lago
+  let result = (|| -> io::Result<()> {
+    for _ in 0..fleet {
+      let mut spec = Launcher::child_spec(PromptSelection::Ship)?;
+      spec.policy(RestartPolicy::OnFailure);
+      supervisor.set_spawn_options(entity_spawn_options(&group));
+      let child = supervisor.add(&spec)?;
+      group.add(child)?;
+      report.launched(child, PromptSelection::Ship);
+      tui.print_launch(PromptSelection::Ship, child);
+      child_counter += 1;
     }
-    match selection {
-      Some(PromptSelection::Exit) => quit = true,
-      // Si tengo una opción válida
-      Some(value) => {
-        let result = process::fork();
-        match result {
-          Ok(process::ForkResult::Parent{child}) => {
-            log!(format!("El hijo {:?} fue lanzado", child).as_str(), &LogSeverity::INFO);
-            tui.print_launch(value, child);
+    while !quit {
+      // Levanto las opciones pasadas por argumento
+      let mut selection = selection_vector.pop();
+      if selection.is_none() {
+        // Si ya levanté todas, le permito al usuario
+        selection = tui.prompt();
+      }
+      match selection {
+        Some(PromptSelection::Exit) => quit = true,
+        Some(PromptSelection::Idle) => {},
+        // Si tengo una opción válida, lanzo la entidad
+        Some(value) => {
+          let launched = if exec_mode {
+            // En modo exec, como un programa separado
+            Launcher::spawn(value, &entity_spawn_options(&group))
+          } else {
+            // Si no, en una copia del lanzador
+            process::spawn_fn(|| {
+              group.join()?;
+              process::set_parent_death_signal(libc::SIGTERM, launcher_pid)?;
+              let result = Launcher::launch(&mut runner, value);
+              let msg = format!("El proceso {:?} terminó con resultado {:?}", pid(), result);
+              log!(msg.as_str(), &LogSeverity::INFO);
+              result
+            }).map(|child| child.pid())
+          };
+          match launched {
+            Ok(child) => {
+              group.add(child)?;
+              report.launched(child, value);
+              log!(format!("El hijo {:?} fue lanzado", child).as_str(), &LogSeverity::INFO);
+              tui.print_launch(value, child);
+              child_counter += 1;
+            },
+            Err(e) => {
+              log!(format!("No se pudo lanzar {:?}: {}", value, e).as_str(), &LogSeverity::ERROR);
+            }
+          }
+        },
+        None => tui.print_invalid_input()
+      }
+      for event in reaper.poll()? {
+        log_child_exit(&tui, event.pid, event.status);
+        report.exited(event.pid, &event.usage);
+        child_counter -= 1;
+        // Mientras no se esté cerrando la aplicación, relanzo la flota
+        if quit || quit_handler.lock().unwrap().has_graceful_quit() {
+          continue;
+        }
+        supervisor.set_spawn_options(entity_spawn_options(&group));
+        match supervisor.handle_exit(event.pid, event.status) {
+          Ok(ExitAction::Restarted(child)) => {
+            group.add(child)?;
+            report.launched(child, PromptSelection::Ship);
+            tui.print_launch(PromptSelection::Ship, child);
             child_counter += 1;
           },
-          Ok(process::ForkResult::Child) => {
-            quit = true;
-            child_result = Some(Launcher::launch(&mut runner, value));
-          },
-          Err(_e) => {
-            child_result = Some(Err(io::Error::last_os_error()));
+          Ok(_) => {},
+          // Si no se pudo relanzar, el barco se da por perdido y sigo uniendo
+          Err(e) => {
+            let msg = format!("No se pudo reiniciar el barco {}, se abandona: {}", event.pid, e);
+            log!(msg.as_str(), &LogSeverity::ERROR);
           }
         }
-      },
-      None => tui.print_invalid_input()
+      }
+      SignalHandlerDispatcher::dispatch_pending();
+      quit = quit || quit_handler.lock().unwrap().has_graceful_quit();
+    }
+    Ok(())
+  })();
+  if let Err(ref e) = result {
+    log!(format!("Error en el lanzador: {}", e).as_str(), &LogSeverity::ERROR);
+  }
+  // El padre termina a todas las entidades y hace join de todos los hijos.
+  let terminated = terminate_entities(&tui, &group, &mut report, child_counter);
+  report.log();
+  log!("Terminando la aplicación", &LogSeverity::INFO);
+  runner.exit()?;
+  result.and(terminated)
+}
+
+// Pide a las entidades del grupo que terminen y las une. Si alguna no termina
+// dentro de QUIT_TIMEOUT_SECS (por ejemplo, bloqueada abriendo un FIFO), se
+// las mata con SIGKILL.
+fn terminate_entities(tui: &Tui, group: &ProcessGroup, report: &mut UsageReport,
+    mut child_counter: i32) -> io::Result<()> {
+  if child_counter > 0 {
+    if let Err(e) = group.signal(libc::SIGTERM) {
+      log!(format!("No se pudo terminar a las entidades: {}", e).as_str(), &LogSeverity::WARN);
     }
-    quit = quit || quit_handler.borrow().has_graceful_quit();
   }
-  // Fin del programa para los procesos hijos.
-  if let Some(result) = child_result {
-    let msg = format!("El proceso {:?} fue terminó con resultado {:?}", pid(), result);
-    log!(msg.as_str(), &LogSeverity::INFO);
-    result
-  } else {
-  // El padre hace join de todos los hijos.
-    for _ in 0..child_counter {
-      let child_pid = process::waitpid(process::ANY_CHILD)?;
-      log!(format!("El hijo {:?} fue unido", child_pid).as_str(), &LogSeverity::INFO);
+  let deadline = Instant::now() + Duration::from_secs(QUIT_TIMEOUT_SECS);
+  let mut killed = false;
+  while child_counter > 0 {
+    let joined = match process::try_wait4(process::ANY_CHILD) {
+      Ok(joined) => joined,
+      // El contador quedó desfasado: no queda ningún hijo por unir
+      Err(ref e) if e.raw_os_error() == Some(libc::ECHILD) => {
+        let msg = format!("No quedan hijos por unir, se esperaban {}", child_counter);
+        log!(msg.as_str(), &LogSeverity::WARN);
+        break;
+      },
+      Err(e) => return Err(e)
+    };
+    match joined {
+      Some((child_pid, status, usage)) => {
+        log_child_exit(tui, child_pid, status);
+        report.exited(child_pid, &usage);
+        child_counter -= 1;
+      },
+      None if !killed && Instant::now() >= deadline => {
+        log!("Las entidades no terminaron a tiempo, enviando SIGKILL", &LogSeverity::WARN);
+        group.signal(libc::SIGKILL)?;
+        killed = true;
+      },
+      None => thread::sleep(Duration::from_millis(QUIT_POLL_MILLIS))
     }
-    log!("Terminando la aplicación", &LogSeverity::INFO);
-    runner.exit()
   }
+  Ok(())
+}
+
+// Opciones para lanzar entidades dentro del grupo, que terminan si el
+// lanzador muere
+fn entity_spawn_options(group: &ProcessGroup) -> SpawnOptions {
+  let mut options = group.spawn_options();
+  options.parent_death_signal(libc::SIGTERM);
+  options
+}
+
+// Informa que un hijo fue unido y cómo terminó
+fn log_child_exit(tui: &Tui, child_pid: libc::pid_t, status: process::ExitStatus) {
+  let severity = if status.success() { LogSeverity::INFO } else { LogSeverity::WARN };
+  log!(format!("El hijo {:?} fue unido: {}", child_pid, status).as_str(), &severity);
+  tui.print_exit(child_pid, status);
 }
 
 // Convierto el mapa a un vector de selecciones
@@ -1,10 +1,16 @@
+use concurrentes::log::{DEFAULT_LOG_PATH, LOG_LEVEL_VAR, LOG_PATH_VAR, Log, LogSeverity};
+
 use std::collections::HashMap;
-use getopts::Options;
+use std::env;
+use getopts::{Matches, Options};
 
 /// Parser de argumentos
 ///
 /// Interpreta los argumentos con los que es invocada la aplicación.
-/// Permite asignar la cantidad inicial de barcos, pasajeros y viajantes.
+/// Permite asignar la cantidad inicial de barcos, pasajeros y viajantes, la
+/// cantidad de barcos supervisados (flota) y si las entidades se lanzan como
+/// programas separados (`exec`), si el lanzador corre en segundo plano
+/// como daemon, y el archivo y la severidad mínima del log.
 /// Utiliza un wrapper de getopt (biblioteca de C)
 pub struct ArgsParser {
   opts: Options
@@ -18,6 +24,11 @@ impl ArgsParser {
     opts.optopt("s", "ships", "set initial ships", "<num>");
     opts.optopt("p", "passenger", "set initial passengers", "<num>");
     opts.optopt("t", "traveller", "set initial travellers", "<num>");
+    opts.optopt("f", "fleet", "set ships kept alive by the supervisor", "<num>");
+    opts.optflag("x", "exec", "launch entities as separate binaries");
+    opts.optflag("d", "daemon", "run in the background without user interface");
+    opts.optopt("l", "log", "write the log to this file", "<file>");
+    opts.optopt("L", "log-level", "minimum log severity (ERROR, WARN, INFO, DEBUG)", "<level>");
     opts.optflag("h", "help", "print this help menu");
 
     ArgsParser {opts}
@@ -37,18 +48,46 @@ impl ArgsParser {
     if matches.opt_present("h") {
       self.print_help(&program);
       None
+    } else if let Err(msg) = ArgsParser::init_log(&matches) {
+      println!("{}", msg);
+      self.print_help(&program);
+      None
     } else {
       let mut map = HashMap::new();
       let num_ships = matches.opt_get_default("s", 0).expect("Invalid ship number");
       let num_passengers = matches.opt_get_default("p", 0).expect("Invalid passenger number");
       let num_travellers = matches.opt_get_default("t", 0).expect("Invalid travellers number");
+      let num_fleet = matches.opt_get_default("f", 0).expect("Invalid fleet number");
       map.insert(String::from("ships"), num_ships);
       map.insert(String::from("passengers"), num_passengers);
       map.insert(String::from("travellers"), num_travellers);
+      map.insert(String::from("fleet"), num_fleet);
+      map.insert(String::from("exec"), matches.opt_present("x") as i32);
+      map.insert(String::from("daemon"), matches.opt_present("d") as i32);
       Some(map)
     }
   }
 
+  /// Configura el log global con `--log` y `--log-level`, si se pasaron.
+  /// Los exporta también a las variables de entorno, para que los usen las
+  /// entidades lanzadas como programas separados; si las variables ya
+  /// estaban definidas, tienen prioridad.
+  fn init_log(matches: &Matches) -> Result<(), String> {
+    if !matches.opt_present("l") && !matches.opt_present("L") {
+      return Ok(());
+    }
+    let path = matches.opt_str("l").unwrap_or_else(|| DEFAULT_LOG_PATH.to_string());
+    let level = matches.opt_str("L").unwrap_or_else(|| "DEBUG".to_string());
+    let min_severity: LogSeverity = level.parse()?;
+    if env::var_os(LOG_PATH_VAR).is_none() {
+      env::set_var(LOG_PATH_VAR, &path);
+    }
+    if env::var_os(LOG_LEVEL_VAR).is_none() {
+      env::set_var(LOG_LEVEL_VAR, &level);
+    }
+    Log::init(&path, min_severity).map_err(|e| format!("No se pudo abrir el log {}: {}", path, e))
+  }
+
   /// Escribe por salida standard la sintaxis esperada de los argumentos
   pub fn print_help(self, program: &str) {
     let brief = format!("Usage: {} FILE [options]", program);
@@ -1,4 +1,13 @@
+use concurrentes::process;
+use concurrentes::process::SpawnOptions;
+use concurrentes::supervisor::ChildSpec;
+
+use libc::pid_t;
+
+use std::env;
 use std::io;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
 
 use live_objects::{live_object, ship::Ship, passenger::Passenger, inspector::Inspector};
 
@@ -10,6 +19,9 @@ use misc::tui::PromptSelection;
 /// * Barco
 /// * Pasajero
 /// * Inspector
+///
+/// Las entidades pueden correr dentro de una copia del lanzador (`launch`) o
+/// como programas separados (`spawn`), ubicados junto al ejecutable actual.
 pub struct Launcher;
 
 impl Launcher {
@@ -18,26 +30,65 @@ impl Launcher {
     match selection {
       PromptSelection::Ship => {
         let current_port = runner.get_random_port();
-        let ship = Ship::new(2, current_port);
+        let ship = Ship::new(2, current_port)?;
         runner.run(ship)
       },
       PromptSelection::Passenger => {
         let destination = runner.get_random_port();
         let current_port = runner.get_random_port();
-        let passenger = Passenger::new(current_port, destination);
+        let passenger = Passenger::new(current_port, destination)?;
         runner.run(passenger)
       },
       PromptSelection::Inspector => {
         let current_port = runner.get_random_port();
-        let inspector = Inspector::new(current_port, true);
+        let inspector = Inspector::new(current_port, true)?;
         runner.run(inspector)
       },
       PromptSelection::Navy => {
         let current_port = runner.get_random_port();
-        let inspector = Inspector::new(current_port, false);
+        let inspector = Inspector::new(current_port, false)?;
         runner.run(inspector)
       },
-      PromptSelection::Exit => unreachable!()
+      PromptSelection::Exit | PromptSelection::Idle => unreachable!()
+    }
+  }
+
+  /// Ejecuta la entidad seleccionada como un programa separado, y devuelve
+  /// el pid del nuevo proceso
+  pub fn spawn(selection: PromptSelection, options: &SpawnOptions) -> io::Result<pid_t> {
+    let (binary, args) = Launcher::binary(selection);
+    let program = Launcher::program_path(binary)?;
+    process::spawn_with_options(program.to_string_lossy().as_ref(), args, &[], options)
+  }
+
+  /// Especificación para que un supervisor lance la entidad seleccionada
+  /// como un programa separado
+  pub fn child_spec(selection: PromptSelection) -> io::Result<ChildSpec> {
+    let (binary, args) = Launcher::binary(selection);
+    let program = Launcher::program_path(binary)?;
+    let mut spec = ChildSpec::new(binary, program.to_string_lossy().as_ref());
+    for arg in args {
+      spec.arg(arg);
     }
+    Ok(spec)
+  }
+
+  /// Programa y argumentos que ejecutan la entidad seleccionada
+  fn binary(selection: PromptSelection) -> (&'static str, &'static [&'static str]) {
+    match selection {
+      PromptSelection::Ship => ("ship", &[]),
+      PromptSelection::Passenger => ("passenger", &[]),
+      PromptSelection::Inspector => ("inspector", &[]),
+      PromptSelection::Navy => ("inspector", &["--navy"]),
+      PromptSelection::Exit | PromptSelection::Idle => unreachable!()
+    }
+  }
+
+  /// Ruta de un programa ubicado junto al ejecutable actual
+  fn program_path(binary: &str) -> io::Result<PathBuf> {
+    let exe = env::current_exe()?;
+    let dir = exe.parent()
+      .ok_or_else(|| Error::new(ErrorKind::NotFound, "Directorio del lanzador inválido"))?;
+    Ok(dir.join(binary))
   }
 }
@@ -9,3 +9,5 @@ pub mod config;
 pub mod launcher;
 /// Interfaz de texto
 pub mod tui;
+/// Reporte de consumo de recursos de las entidades
+pub mod usage_report;
@@ -1,34 +1,61 @@
 use std::collections::HashMap;
 use ncurses;
 
+use concurrentes::process::ExitStatus;
+
 use std::char::from_u32;
 use std::ops::Drop;
 use std::cell::RefCell;
+use std::thread;
+use std::time::Duration;
+
+/// Tiempo máximo (en milisegundos) que el menú espera una opción del usuario
+const PROMPT_TIMEOUT_MS: i32 = 500;
 
 /// Opciones disponibles en la interfaz de usuario
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum PromptSelection {
   Ship,
   Passenger,
   Inspector,
   Navy,
-  Exit
+  Exit,
+  /// El usuario no ingresó ninguna opción antes del timeout
+  Idle
 }
 
 /// Interfaz de texto de usuario, hecha con ncurses
+///
+/// En modo sin pantalla (`headless`, usado cuando el lanzador corre como
+/// daemon) no se inicializa ncurses, no se escribe nada y el usuario nunca
+/// elige una opción.
 pub struct Tui {
   counters: RefCell<HashMap<String, i32>>,
+  headless: bool
 }
 
 impl Tui {
   /// Inicializa la pantalla de ncurses y asigna el mapa de contadores a la interfaz
   pub fn new(counters: RefCell<HashMap<String, i32>>) -> Tui {
     ncurses::initscr();
-    Tui { counters }
+    ncurses::timeout(PROMPT_TIMEOUT_MS);
+    Tui { counters, headless: false }
+  }
+
+  /// Crea una interfaz sin pantalla
+  pub fn headless(counters: RefCell<HashMap<String, i32>>) -> Tui {
+    Tui { counters, headless: true }
   }
 
   /// Escribe el menú del usuario y pregunta por una opción
+  ///
+  /// Si el usuario no ingresa nada en `PROMPT_TIMEOUT_MS` devuelve
+  /// `PromptSelection::Idle`, para que el llamador pueda atender otros eventos.
   pub fn prompt(&self) -> Option<PromptSelection> {
+    if self.headless {
+      thread::sleep(Duration::from_millis(PROMPT_TIMEOUT_MS as u64));
+      return Some(PromptSelection::Idle);
+    }
     ncurses::mvprintw(1, 0, "Ingrese un tipo de proceso a lanzar");
     ncurses::mvprintw(2, 0, "1) Barco");
     ncurses::mvprintw(3, 0, "2) Pasajero");
@@ -38,6 +65,9 @@ impl Tui {
     ncurses::mv(8, 0);
     ncurses::refresh();
     let input_raw = ncurses::getch();
+    if input_raw == ncurses::ERR {
+      return Some(PromptSelection::Idle);
+    }
     if let Some(input) = from_u32(input_raw as u32) {
       match input {
         '1' => Some(PromptSelection::Ship),
@@ -54,6 +84,9 @@ impl Tui {
 
   /// Informa qué tipo de proceso fue lanzado
   pub fn print_launch(&self, selection: PromptSelection, pid: i32) {
+    if self.headless {
+      return;
+    }
     ncurses::mv(8, 0);
     ncurses::clrtoeol();
     let msg = match selection {
@@ -61,14 +94,28 @@ impl Tui {
       PromptSelection::Ship => format!("Lanzado barco {}", pid),
       PromptSelection::Inspector => format!("Lanzado inspector {}", pid),
       PromptSelection::Navy => format!("Lanzado prefectura {}", pid),
-      PromptSelection::Exit => unreachable!()
+      PromptSelection::Exit | PromptSelection::Idle => unreachable!()
     };
     ncurses::printw(msg.as_str());
     ncurses::refresh();
   }
 
+  /// Informa cómo terminó un proceso lanzado
+  pub fn print_exit(&self, pid: i32, status: ExitStatus) {
+    if self.headless {
+      return;
+    }
+    ncurses::mv(9, 0);
+    ncurses::clrtoeol();
+    ncurses::printw(format!("El proceso {} {}", pid, status).as_str());
+    ncurses::refresh();
+  }
+
   /// Informa entrada inválida
   pub fn print_invalid_input(&self) {
+    if self.headless {
+      return;
+    }
     ncurses::mv(8, 0);
     ncurses::clrtoeol();
     ncurses::printw("El valor ingresado es incorrecto");
@@ -79,6 +126,8 @@ impl Tui {
 impl Drop for Tui {
   /// Libera ncurses
   fn drop(&mut self) {
-    ncurses::endwin();
+    if !self.headless {
+      ncurses::endwin();
+    }
   }
 }
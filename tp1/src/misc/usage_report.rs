@@ -0,0 +1,69 @@
+use concurrentes::log::{GLOBAL_LOG, LogSeverity};
+use concurrentes::process::ResourceUsage;
+
+use libc::pid_t;
+
+use misc::tui::PromptSelection;
+
+use std::collections::HashMap;
+
+/// Consumo acumulado de las entidades de un mismo tipo
+#[derive(Default)]
+struct EntityUsage {
+  processes: u32,
+  usage: ResourceUsage
+}
+
+/// Reporte de consumo de recursos por tipo de entidad
+///
+/// El lanzador anota el tipo de cada hijo al lanzarlo y, al unirlo, el
+/// consumo informado por `wait4`. Al terminar la aplicación escribe un
+/// resumen en el log.
+#[derive(Default)]
+pub struct UsageReport {
+  launched: HashMap<pid_t, PromptSelection>,
+  totals: HashMap<PromptSelection, EntityUsage>
+}
+
+impl UsageReport {
+  /// Crea un reporte vacío
+  pub fn new() -> UsageReport {
+    UsageReport {launched: HashMap::new(), totals: HashMap::new()}
+  }
+
+  /// Anota el tipo de entidad del hijo `pid`
+  pub fn launched(&mut self, pid: pid_t, selection: PromptSelection) {
+    self.launched.insert(pid, selection);
+  }
+
+  /// Acumula el consumo del hijo `pid`, que ya fue unido
+  pub fn exited(&mut self, pid: pid_t, usage: &ResourceUsage) {
+    if let Some(selection) = self.launched.remove(&pid) {
+      let total = self.totals.entry(selection).or_default();
+      total.processes += 1;
+      total.usage.accumulate(usage);
+    }
+  }
+
+  /// Escribe el resumen de consumo por tipo de entidad en el log
+  pub fn log(&self) {
+    for (selection, total) in &self.totals {
+      let msg = format!("Consumo de {} ({} procesos): CPU usuario {:.3}s, CPU sistema {:.3}s, \
+        memoria máxima {} KB, cambios de contexto {} voluntarios / {} involuntarios",
+        entity_name(*selection), total.processes, total.usage.user_time.as_secs_f64(),
+        total.usage.system_time.as_secs_f64(), total.usage.max_rss_kb,
+        total.usage.voluntary_switches, total.usage.involuntary_switches);
+      log!(msg.as_str(), &LogSeverity::INFO);
+    }
+  }
+}
+
+fn entity_name(selection: PromptSelection) -> &'static str {
+  match selection {
+    PromptSelection::Ship => "barcos",
+    PromptSelection::Passenger => "pasajeros",
+    PromptSelection::Inspector => "inspectores",
+    PromptSelection::Navy => "prefectura",
+    PromptSelection::Exit | PromptSelection::Idle => unreachable!()
+  }
+}